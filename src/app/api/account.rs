@@ -1,7 +1,7 @@
+use crate::app::api::book::preset_from_params;
 use crate::app::web::common::HandlerResult;
 use crate::database::model::account::Account;
 use crate::database::model::book::Book;
-use crate::database::model::category::Category;
 use crate::utils::serve_full;
 use cookie::time::Duration;
 use cookie::Cookie;
@@ -74,12 +74,12 @@ pub async fn validate_email(req: Request<Incoming>, pool: PgPool) -> HandlerResu
                 .unwrap());
         }
     };
-    return Ok(Response::builder()
+    Ok(Response::builder()
         .status(StatusCode::CONFLICT)
         .body(serve_full(
             "Email is already taken. Please enter another email. ".as_bytes(),
         ))
-        .unwrap());
+        .unwrap())
 }
 
 pub async fn validate_password(req: Request<Incoming>) -> HandlerResult {
@@ -146,6 +146,16 @@ pub async fn create_account(req: Request<Incoming>, pool: PgPool) -> HandlerResu
             .unwrap());
     }
 
+    let preset = match preset_from_params(&params) {
+        Ok(p) => p,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap())
+        }
+    };
+
     let mut tx = pool.begin().await.unwrap();
     let new_account = Account::new(email, password);
     match sqlx::query(
@@ -178,16 +188,19 @@ pub async fn create_account(req: Request<Incoming>, pool: PgPool) -> HandlerResu
                     .execute(&mut *tx)
                     .await {
                         Ok(_) => {
-                            let categories = Category::batch(id);
+                            let categories = preset.map_or(Vec::new(), |p| p.categories(id));
                             for c in categories {
                                 match sqlx::query(
-                                    "INSERT INTO categories (id, name, description, is_expense, book_id) VALUES ($1, $2, $3, $4, $5)",
+                                    "INSERT INTO categories (id, name, description, is_expense, book_id, parent_id, color, icon) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
                                 )
                                 .bind(c.id.to_bytes())
                                 .bind(c.name)
                                 .bind(c.description)
                                 .bind(c.is_expense)
                                 .bind(c.book_id.to_bytes())
+                                .bind(c.parent_id.map(|id| id.to_bytes()))
+                                .bind(c.color)
+                                .bind(c.icon)
                                 .execute(&mut *tx)
                                 .await {
                                     Ok(_) => {}
@@ -1,13 +1,27 @@
-use super::get_book_default_id;
+use super::book_cookie;
 use crate::{
-    app::web::common::HandlerResult,
+    app::{
+        middlewares::book::{member_guard, role_guard},
+        web::common::HandlerResult,
+    },
     database::{
-        model::book::Book,
-        querier::book::{add_owner_by_email, delete, edit, save},
+        model::{
+            book::{parse_timezone, Book, BookSettings, Role, MAX_MONTH_START_DAY},
+            invitation::Invitation,
+            preset::{Preset, DEFAULT_PRESET},
+        },
+        querier::{
+            book::{
+                delete, duplicate, edit, get_members, get_role, get_settings, remove_member, save,
+                set_default, set_role, DateRange,
+            },
+            invitation,
+        },
     },
     utils::{serve_empty, serve_full},
 };
-use cookie::{time::Duration, Cookie};
+use chrono::NaiveDate;
+use email_address::EmailAddress;
 use http_body_util::BodyExt;
 use hyper::{body::Incoming, header::SET_COOKIE, Request, Response, StatusCode};
 use sqlx::PgPool;
@@ -19,6 +33,31 @@ static EMAILS_MISSING: &[u8] =
 static ID_MISSING: &[u8] = b"missing field: id";
 static NAME_MISSING: &[u8] = b"missing field: name";
 static DESC_MISSING: &[u8] = b"missing field: description";
+static PRESET_NOT_FOUND: &[u8] = b"category preset not found";
+static ROLE_INVALID: &[u8] = b"role must be one of: owner, editor, contributor, viewer";
+static MEMBER_MISSING: &[u8] = b"missing field: account_id";
+static LAST_OWNER: &[u8] = b"a book needs at least one owner";
+static TIMEZONE_INVALID: &[u8] = b"timezone must be a name like Asia/Jakarta or UTC";
+static START_DAY_INVALID: &[u8] = b"month_start_day must be between 1 and 28";
+static RANGE_INVALID: &[u8] =
+    b"records_from and records_to must both be dates like 2026-01-31, from before to";
+static LAST_OWNER_LEAVE: &[u8] =
+    b"you are the last owner, make someone else an owner or delete the book instead";
+
+// reads the optional `preset`, the default preset is used when it is not sent
+// and `none` starts the book without categories
+pub fn preset_from_params(
+    params: &HashMap<String, String>,
+) -> Result<Option<&'static Preset>, &'static [u8]> {
+    match params.get("preset").map(|v| v.as_str()) {
+        None => Ok(Preset::find(DEFAULT_PRESET)),
+        Some("none") => Ok(None),
+        Some(id) => match Preset::find(id) {
+            Some(p) => Ok(Some(p)),
+            None => Err(PRESET_NOT_FOUND),
+        },
+    }
+}
 
 pub async fn create_book(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
     let body = req.collect().await?.to_bytes();
@@ -49,18 +88,31 @@ pub async fn create_book(req: Request<Incoming>, pool: PgPool, account_id: Ulid)
             .body(serve_full(NAME_MISSING))
             .unwrap());
     };
+    let preset = match preset_from_params(&params) {
+        Ok(p) => p,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap())
+        }
+    };
     let new_book = Book::new(name, description);
     let new_book_id = new_book.clone().id;
-    match save(&pool, account_id, new_book).await {
+    let categories = preset.map_or(Vec::new(), |p| p.categories(new_book_id));
+    match save(&pool, account_id, new_book, categories).await {
         Ok(_) => {
             if is_default == "1" {
-                let mut c = Cookie::new("book", new_book_id.to_string());
-                c.set_max_age(Duration::days(30 * 12));
-                c.set_path("/");
+                if let Err(err) = set_default(&pool, account_id, new_book_id).await {
+                    return Ok(Response::builder()
+                        .status(StatusCode::UNPROCESSABLE_ENTITY)
+                        .body(serve_full(err.to_string()))
+                        .unwrap());
+                }
                 Ok(Response::builder()
                     .status(StatusCode::CREATED)
                     .header("HX-Trigger", "createbookSuccess")
-                    .header(SET_COOKIE, c.to_string())
+                    .header(SET_COOKIE, book_cookie(new_book_id))
                     .body(serve_full("Success create a book"))
                     .unwrap())
             } else {
@@ -71,26 +123,35 @@ pub async fn create_book(req: Request<Incoming>, pool: PgPool, account_id: Ulid)
                     .unwrap())
             }
         }
-        Err(err) => {
-            Ok(Response::builder()
-                .status(StatusCode::UNPROCESSABLE_ENTITY)
-                .body(serve_full(err.to_string()))
-                .unwrap())
-        }
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
     }
 }
 
-pub async fn add_book_owner(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
-    let book_id: Ulid;
-    {
-        let header = req.headers();
-        let id = get_book_default_id(header);
-        book_id = id.await.unwrap();
-    }
+// invites people by email, those who are already members are left out
+pub async fn invite_to_book(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
     let body = req.collect().await?.to_bytes();
     let params = form_urlencoded::parse(body.as_ref())
         .into_owned()
         .collect::<HashMap<String, String>>();
+    let book_id = match params.get("book_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_manage).await {
+        return Ok(res);
+    }
     let emails = if let Some(e) = params.get("people") {
         e
     } else {
@@ -99,12 +160,46 @@ pub async fn add_book_owner(req: Request<Incoming>, pool: PgPool, _: Ulid) -> Ha
             .body(serve_full(EMAILS_MISSING))
             .unwrap());
     };
-    let email: Vec<&str> = emails.split(',').collect();
-    match add_owner_by_email(&pool, book_id, email).await {
+    // people invited without a role can change the book but not run it
+    let role = match params.get("role").map(|v| Role::parse(v)) {
+        None => Role::Editor,
+        Some(Some(r)) => r,
+        Some(None) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(ROLE_INVALID))
+                .unwrap())
+        }
+    };
+    let emails: Vec<&str> = emails
+        .split([',', '\n'])
+        .map(|e| e.trim())
+        .filter(|e| !e.is_empty())
+        .collect();
+    if emails.is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(EMAILS_MISSING))
+            .unwrap());
+    }
+    if let Some(e) = emails.iter().find(|e| !EmailAddress::is_valid(e)) {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(format!("{} is not a valid email", e)))
+            .unwrap());
+    }
+    let members = get_members(book_id, &pool).await;
+    let invitations: Vec<Invitation> = emails
+        .iter()
+        .filter(|e| !members.iter().any(|m| m.email.eq_ignore_ascii_case(e)))
+        .map(|e| Invitation::new(book_id, account_id, e, role))
+        .collect();
+    let sent = invitations.len();
+    match invitation::save(&pool, invitations).await {
         Ok(_) => Ok(Response::builder()
             .status(StatusCode::CREATED)
-            .header("HX-Trigger", "addBookOwnerSuccess")
-            .body(serve_full("Success create a book"))
+            .header("HX-Trigger", "inviteSuccess")
+            .body(serve_full(format!("{} invitations sent", sent)))
             .unwrap()),
         Err(err) => Ok(Response::builder()
             .status(StatusCode::UNPROCESSABLE_ENTITY)
@@ -113,7 +208,31 @@ pub async fn add_book_owner(req: Request<Incoming>, pool: PgPool, _: Ulid) -> Ha
     }
 }
 
-pub async fn edit_book(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+// `timezone` and `month_start_day` replace the current settings when sent
+fn settings_from_params(
+    params: &HashMap<String, String>,
+    current: BookSettings,
+) -> Result<BookSettings, &'static [u8]> {
+    let timezone = match params.get("timezone") {
+        Some(v) => parse_timezone(v).ok_or(TIMEZONE_INVALID)?,
+        None => current.timezone,
+    };
+    let month_start_day = match params.get("month_start_day") {
+        Some(v) => v
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|d| (1..=MAX_MONTH_START_DAY).contains(d))
+            .ok_or(START_DAY_INVALID)?,
+        None => current.month_start_day,
+    };
+    Ok(BookSettings {
+        timezone,
+        month_start_day,
+    })
+}
+
+pub async fn edit_book(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
     let body = req.collect().await?.to_bytes();
     let params = form_urlencoded::parse(body.as_ref())
         .into_owned()
@@ -142,11 +261,25 @@ pub async fn edit_book(req: Request<Incoming>, pool: PgPool, _: Ulid) -> Handler
             .body(serve_full(DESC_MISSING))
             .unwrap());
     };
+    let book_id = Ulid::from_string(id).unwrap();
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_manage).await {
+        return Ok(res);
+    }
+    let settings = match settings_from_params(&params, get_settings(&pool, book_id).await) {
+        Ok(s) => s,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap())
+        }
+    };
     match edit(
         &pool,
         name.to_string(),
         description.to_string(),
-        Ulid::from_string(id).unwrap(),
+        settings,
+        book_id,
     )
     .await
     {
@@ -175,8 +308,11 @@ pub async fn delete_book(req: Request<Incoming>, pool: PgPool, account_id: Ulid)
             .body(serve_empty())
             .unwrap());
     };
-    let book_id = Ulid::from_string(book).unwrap().to_bytes();
-    match delete(&pool, book_id.into(), account_id).await {
+    let book_id = Ulid::from_string(book).unwrap();
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_manage).await {
+        return Ok(res);
+    }
+    match delete(&pool, book_id, account_id).await {
         Ok(_) => Ok(Response::builder()
             .status(StatusCode::OK)
             .header("HX-Trigger", "bookChangeSuccess")
@@ -188,3 +324,268 @@ pub async fn delete_book(req: Request<Incoming>, pool: PgPool, account_id: Ulid)
             .unwrap()),
     }
 }
+
+// changes the role of a member, the last owner of a book cannot step down
+pub async fn edit_member_role(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let book_id = match params.get("book_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_manage).await {
+        return Ok(res);
+    }
+    let member_id = match params.get("account_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(MEMBER_MISSING))
+                .unwrap())
+        }
+    };
+    let role = match params.get("role").and_then(|v| Role::parse(v)) {
+        Some(r) => r,
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(ROLE_INVALID))
+                .unwrap())
+        }
+    };
+    if get_role(&pool, book_id, member_id).await.is_none() {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(serve_empty())
+            .unwrap());
+    }
+    match set_role(&pool, book_id, member_id, role).await {
+        Ok(true) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "memberChangeSuccess")
+            .body(serve_full("Saved"))
+            .unwrap()),
+        Ok(false) => Ok(Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(serve_full(LAST_OWNER))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+// takes a member out of the book, owners only
+pub async fn delete_member(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let book_id = match params.get("book_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_manage).await {
+        return Ok(res);
+    }
+    let member_id = match params.get("account_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(MEMBER_MISSING))
+                .unwrap())
+        }
+    };
+    if get_role(&pool, book_id, member_id).await.is_none() {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(serve_empty())
+            .unwrap());
+    }
+    match remove_member(&pool, book_id, member_id).await {
+        Ok(true) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "memberChangeSuccess")
+            .body(serve_full("Member removed"))
+            .unwrap()),
+        Ok(false) => Ok(Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(serve_full(LAST_OWNER))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+// takes the account itself out of the book, whatever its role
+pub async fn leave_book(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let book_id = match params.get("book_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    if let Err(res) = member_guard(&pool, account_id, book_id).await {
+        return Ok(res);
+    }
+    match remove_member(&pool, book_id, account_id).await {
+        Ok(true) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "bookChangeSuccess")
+            .body(serve_full("You left the book"))
+            .unwrap()),
+        Ok(false) => Ok(Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(serve_full(LAST_OWNER_LEAVE))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+// makes the book the active one, in this browser and on the account so other
+// devices pick it up at their next login
+pub async fn switch_book(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let book_id = match params.get("book_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    if let Err(res) = member_guard(&pool, account_id, book_id).await {
+        return Ok(res);
+    }
+    match set_default(&pool, account_id, book_id).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "bookSwitchSuccess")
+            .header(SET_COOKIE, book_cookie(book_id))
+            .body(serve_full("Book switched"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+// reads the optional range of records to copy, both ends are dates in the
+// source book's timezone and the last day is included
+fn records_range_from_params(
+    params: &HashMap<String, String>,
+    settings: &BookSettings,
+) -> Result<Option<DateRange>, &'static [u8]> {
+    let date = |key: &str| {
+        params
+            .get(key)
+            .filter(|v| !v.is_empty())
+            .map(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d"))
+    };
+    match (date("records_from"), date("records_to")) {
+        (None, None) => Ok(None),
+        (Some(Ok(from)), Some(Ok(to))) if from <= to => Ok(Some((
+            settings.midnight(from),
+            settings.midnight(to + chrono::Duration::days(1)),
+        ))),
+        _ => Err(RANGE_INVALID),
+    }
+}
+
+// starts a new book from the structure of an existing one, owners only since
+// the members come along
+pub async fn duplicate_book(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let source_id = match params.get("book_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    if let Err(res) = role_guard(&pool, account_id, source_id, Role::can_manage).await {
+        return Ok(res);
+    }
+    let name = match params.get("name").filter(|v| !v.trim().is_empty()) {
+        Some(e) => e,
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(NAME_MISSING))
+                .unwrap())
+        }
+    };
+    let description = params.get("description").map_or("", |v| v.as_str());
+    let settings = get_settings(&pool, source_id).await;
+    let records = match records_range_from_params(&params, &settings) {
+        Ok(r) => r,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap())
+        }
+    };
+    let new_book = Book::new(name, description);
+    let new_book_id = new_book.id;
+    match duplicate(&pool, source_id, new_book, records).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .header("HX-Trigger", "bookChangeSuccess")
+            .body(serve_full(format!("Book copied to {}", new_book_id)))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
@@ -1,23 +1,101 @@
 use std::collections::HashMap;
 
+use askama::Template;
 use http_body_util::BodyExt;
-use hyper::{body::Incoming, Request, Response, StatusCode};
+use hyper::{body::Incoming, header::CONTENT_TYPE, Request, Response, StatusCode};
 use sqlx::PgPool;
 use ulid::Ulid;
 
 use crate::{
-    app::web::common::HandlerResult,
+    app::{
+        api::version_from_params,
+        middlewares::book::role_guard,
+        web::{common::HandlerResult, templates::CategoryConflictTemplate},
+    },
     database::{
-        model::category::Category,
-        querier::category::{delete, edit, save},
+        model::{
+            book::Role,
+            category::{is_valid_color, Category, DEFAULT_COLOR, ICONS},
+        },
+        querier::category::{archive, delete, edit, get_by_book_id, get_by_id, merge, save},
     },
     utils::{serve_empty, serve_full},
 };
 
 static NAME_MISSING: &[u8] = b"missing field: name";
 static DESC_MISSING: &[u8] = b"missing field: description";
+static COLOR_INVALID: &[u8] = b"color must look like #1e293b";
+static ICON_INVALID: &[u8] = b"icon is not one of the available icons";
+static PARENT_NOT_FOUND: &[u8] = b"parent category not found in this book";
+static PARENT_WRONG_TYPE: &[u8] = b"a sub-category must have the same type as its parent";
+static MERGE_MISSING: &[u8] = b"missing field: source_id or target_id";
+static MERGE_SAME: &[u8] = b"a category cannot be merged into itself";
+static MERGE_OTHER_BOOK: &[u8] = b"categories can only be merged within the same book";
+static MERGE_WRONG_TYPE: &[u8] = b"an income category cannot be merged with an expense category";
+static MERGE_SUBTREE: &[u8] = b"a category cannot be merged into one of its sub-categories";
+static CATEGORY_IN_USE: &[u8] =
+    b"this category still has records, choose a replacement category or delete the records too";
+static REPLACEMENT_INVALID: &[u8] =
+    b"the replacement must be another category of the same book and type";
+static PARENT_CYCLE: &[u8] = b"a category cannot be placed under itself or its sub-categories";
+
+// reads the optional `parent_id`, an empty value means a top-level category
+// and a missing one keeps the `current` parent
+fn parent_from_params(
+    params: &HashMap<String, String>,
+    current: Option<Ulid>,
+) -> Result<Option<Ulid>, &'static [u8]> {
+    match params.get("parent_id").map(|v| v.as_str()) {
+        Some("") => Ok(None),
+        Some(v) => match Ulid::from_string(v) {
+            Ok(id) => Ok(Some(id)),
+            Err(_) => Err(PARENT_NOT_FOUND),
+        },
+        None => Ok(current),
+    }
+}
+
+// reads `color` and `icon`, keeping the given values when they are not sent
+fn appearance_from_params(
+    params: &HashMap<String, String>,
+    color: &str,
+    icon: &str,
+) -> Result<(String, String), &'static [u8]> {
+    let color = params.get("color").map_or(color, |v| v.as_str());
+    if !is_valid_color(color) {
+        return Err(COLOR_INVALID);
+    }
+    let icon = params.get("icon").map_or(icon, |v| v.as_str());
+    if !ICONS.contains(&icon) {
+        return Err(ICON_INVALID);
+    }
+    Ok((color.to_lowercase(), icon.to_string()))
+}
+
+// a parent must live in the same book and have the same type as its child
+async fn check_parent(
+    pool: &PgPool,
+    parent_id: Ulid,
+    book_id: Ulid,
+    is_expense: bool,
+) -> Result<(), &'static [u8]> {
+    match get_by_id(parent_id, pool.clone()).await {
+        Some(parent) if parent.book_id == book_id => {
+            if parent.is_expense == is_expense {
+                Ok(())
+            } else {
+                Err(PARENT_WRONG_TYPE)
+            }
+        }
+        _ => Err(PARENT_NOT_FOUND),
+    }
+}
 
-pub async fn create_category(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+pub async fn create_category(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
     let body = req.collect().await?.to_bytes();
     let params = form_urlencoded::parse(body.as_ref())
         .into_owned()
@@ -47,6 +125,16 @@ pub async fn create_category(req: Request<Incoming>, pool: PgPool, _: Ulid) -> H
             .unwrap());
     };
     let book_id = Ulid::from_string(book).unwrap().to_bytes();
+    if let Err(res) = role_guard(
+        &pool,
+        account_id,
+        Ulid::from_bytes(book_id),
+        Role::can_organize,
+    )
+    .await
+    {
+        return Ok(res);
+    }
     let category_type = if let Some(e) = params.get("type") {
         e
     } else {
@@ -56,25 +144,94 @@ pub async fn create_category(req: Request<Incoming>, pool: PgPool, _: Ulid) -> H
             .unwrap());
     };
     let is_expense: bool = category_type == "expense";
-    let new_category = Category::new(name, description, is_expense, Ulid::from_bytes(book_id));
-    match save(&pool, new_category).await {
-        Ok(_) => {
-            Ok(Response::builder()
-                .status(StatusCode::CREATED)
-                .header("HX-Trigger", "createcategorySuccess")
-                .body(serve_full("Success create a category"))
+    let parent_id = match parent_from_params(&params, None) {
+        Ok(v) => v,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
                 .unwrap())
         }
+    };
+    if let Some(id) = parent_id {
+        if let Err(err) = check_parent(&pool, id, Ulid::from_bytes(book_id), is_expense).await {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap());
+        }
+    }
+    let (color, icon) = match appearance_from_params(&params, DEFAULT_COLOR, "") {
+        Ok(v) => v,
         Err(err) => {
-            Ok(Response::builder()
+            return Ok(Response::builder()
                 .status(StatusCode::UNPROCESSABLE_ENTITY)
-                .body(serve_full(err.to_string()))
+                .body(serve_full(err))
                 .unwrap())
         }
+    };
+    let mut new_category = Category::new(
+        name,
+        description,
+        is_expense,
+        Ulid::from_bytes(book_id),
+        parent_id,
+    );
+    new_category.color = color;
+    new_category.icon = icon;
+    match save(&pool, new_category).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .header("HX-Trigger", "createcategorySuccess")
+            .body(serve_full("Success create a category"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
     }
 }
 
-pub async fn edit_category(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+// answers an edit made on an older version of the category with the category
+// as it is now, so the change can be checked and sent again
+async fn category_conflict(pool: &PgPool, category_id: Ulid) -> HandlerResult {
+    let current = match get_by_id(category_id, pool.clone()).await {
+        Some(c) => c,
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    let parent_name = match current.parent_id {
+        Some(id) => get_by_id(id, pool.clone())
+            .await
+            .map_or("".to_string(), |p| p.name),
+        None => "".to_string(),
+    };
+    let template = CategoryConflictTemplate {
+        id: current.id.to_string(),
+        name: current.name,
+        description: current.description,
+        is_expense: current.is_expense,
+        parent_name,
+        color: current.color,
+        icon: current.icon,
+        version: current.version,
+    };
+    Ok(Response::builder()
+        .status(StatusCode::CONFLICT)
+        .header(CONTENT_TYPE, "text/html")
+        .body(serve_full(template.render().expect("Should render markup")))
+        .unwrap())
+}
+
+pub async fn edit_category(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
     let body = req.collect().await?.to_bytes();
     let params = form_urlencoded::parse(body.as_ref())
         .into_owned()
@@ -103,19 +260,79 @@ pub async fn edit_category(req: Request<Incoming>, pool: PgPool, _: Ulid) -> Han
             .body(serve_empty())
             .unwrap());
     };
-    match edit(
-        &pool,
-        name.to_string(),
-        description.to_string(),
-        Ulid::from_string(category_id_str).unwrap(),
-    )
-    .await
-    {
-        Ok(_) => Ok(Response::builder()
+    let mut category =
+        match get_by_id(Ulid::from_string(category_id_str).unwrap(), pool.clone()).await {
+            Some(c) => c,
+            None => {
+                return Ok(Response::builder()
+                    .status(StatusCode::UNPROCESSABLE_ENTITY)
+                    .body(serve_empty())
+                    .unwrap())
+            }
+        };
+    if let Err(res) = role_guard(&pool, account_id, category.book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    // the type may change, the warning about its effect on reports is shown
+    // in the form
+    if let Some(v) = params.get("type") {
+        category.is_expense = v == "expense";
+    }
+    let (color, icon) = match appearance_from_params(&params, &category.color, &category.icon) {
+        Ok(v) => v,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap())
+        }
+    };
+    let parent_id = match parent_from_params(&params, category.parent_id) {
+        Ok(v) => v,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap())
+        }
+    };
+    if let Some(id) = parent_id {
+        if let Err(err) = check_parent(&pool, id, category.book_id, category.is_expense).await {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap());
+        }
+        let categories = get_by_book_id(category.book_id, pool.clone()).await;
+        if category.would_cycle(id, &categories) {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(PARENT_CYCLE))
+                .unwrap());
+        }
+    }
+    category.version = match version_from_params(&params) {
+        Ok(v) => v,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap())
+        }
+    };
+    let category_id = category.id;
+    category.name = name.to_string();
+    category.description = description.to_string();
+    category.parent_id = parent_id;
+    category.color = color;
+    category.icon = icon;
+    match edit(&pool, category).await {
+        Ok(true) => Ok(Response::builder()
             .status(StatusCode::OK)
             .header("HX-Trigger", "categoryChangeSuccess")
             .body(serve_full("Success edit a category"))
             .unwrap()),
+        Ok(false) => category_conflict(&pool, category_id).await,
         Err(err) => Ok(Response::builder()
             .status(StatusCode::UNPROCESSABLE_ENTITY)
             .body(serve_full(err.to_string()))
@@ -123,7 +340,13 @@ pub async fn edit_category(req: Request<Incoming>, pool: PgPool, _: Ulid) -> Han
     }
 }
 
-pub async fn delete_category(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+// a category that still has records needs `replacement_id`, the category its
+// records move to, or `replacement_id=delete` to delete the records with it
+pub async fn delete_category(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
     let body = req.collect().await?.to_bytes();
     let params = form_urlencoded::parse(body.as_ref())
         .into_owned()
@@ -136,18 +359,182 @@ pub async fn delete_category(req: Request<Incoming>, pool: PgPool, _: Ulid) -> H
             .body(serve_empty())
             .unwrap());
     };
-    let category_id = Ulid::from_string(category).unwrap();
-    match delete(&pool, category_id).await {
+    let category = match get_by_id(Ulid::from_string(category).unwrap(), pool.clone()).await {
+        Some(c) => c,
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    if let Err(res) = role_guard(&pool, account_id, category.book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let replacement_id = match params
+        .get("replacement_id")
+        .filter(|v| !v.is_empty())
+        .map(|v| v.as_str())
+    {
+        None if category.record_count > 0 => {
+            return Ok(Response::builder()
+                .status(StatusCode::CONFLICT)
+                .body(serve_full(CATEGORY_IN_USE))
+                .unwrap())
+        }
+        None | Some("delete") => None,
+        Some(v) => {
+            let replacement = match Ulid::from_string(v) {
+                Ok(id) => get_by_id(id, pool.clone()).await,
+                Err(_) => None,
+            };
+            match replacement {
+                Some(r)
+                    if r.id != category.id
+                        && r.book_id == category.book_id
+                        && r.is_expense == category.is_expense =>
+                {
+                    Some(r.id)
+                }
+                _ => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::UNPROCESSABLE_ENTITY)
+                        .body(serve_full(REPLACEMENT_INVALID))
+                        .unwrap())
+                }
+            }
+        }
+    };
+    match delete(&pool, category.id, replacement_id).await {
         Ok(_) => Ok(Response::builder()
             .status(StatusCode::OK)
             .header("HX-Trigger", "categoryChangeSuccess")
             .body(serve_full("Success delete a category"))
             .unwrap()),
-        Err(err) => {
-            Ok(Response::builder()
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+pub async fn merge_category(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let (source_id, target_id) = match (
+        params.get("source_id").map(|v| Ulid::from_string(v)),
+        params.get("target_id").map(|v| Ulid::from_string(v)),
+    ) {
+        (Some(Ok(source)), Some(Ok(target))) => (source, target),
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(MERGE_MISSING))
+                .unwrap())
+        }
+    };
+    if source_id == target_id {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(MERGE_SAME))
+            .unwrap());
+    }
+    let (source, target) = match (
+        get_by_id(source_id, pool.clone()).await,
+        get_by_id(target_id, pool.clone()).await,
+    ) {
+        (Some(source), Some(target)) => (source, target),
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(MERGE_MISSING))
+                .unwrap())
+        }
+    };
+    if let Err(res) = role_guard(&pool, account_id, source.book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    if source.book_id != target.book_id {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(MERGE_OTHER_BOOK))
+            .unwrap());
+    }
+    if source.is_expense != target.is_expense {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(MERGE_WRONG_TYPE))
+            .unwrap());
+    }
+    // the sub-categories of the source move under the target, which must not
+    // be one of them
+    let categories = get_by_book_id(source.book_id, pool.clone()).await;
+    if source.would_cycle(target.id, &categories) {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(MERGE_SUBTREE))
+            .unwrap());
+    }
+    match merge(&pool, source.id, target.id).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "categoryChangeSuccess")
+            .body(serve_full("Success merge a category"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+// `archived=1` archives the category and its sub-categories, `archived=0`
+// brings them back to the record forms
+pub async fn archive_category(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let category = match params.get("category_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => get_by_id(id, pool.clone()).await,
+        _ => None,
+    };
+    let category = match category {
+        Some(c) => c,
+        None => {
+            return Ok(Response::builder()
                 .status(StatusCode::UNPROCESSABLE_ENTITY)
-                .body(serve_full(err.to_string()))
+                .body(serve_empty())
                 .unwrap())
         }
+    };
+    if let Err(res) = role_guard(&pool, account_id, category.book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let archived = params.get("archived").is_some_and(|v| v == "1");
+    match archive(&pool, category.id, archived).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "categoryChangeSuccess")
+            .body(serve_full(if archived {
+                "Success archive a category"
+            } else {
+                "Success restore a category"
+            }))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
     }
 }
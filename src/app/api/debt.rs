@@ -0,0 +1,261 @@
+use crate::{
+    app::{middlewares::book::role_guard, web::common::HandlerResult},
+    database::{
+        model::{
+            book::Role,
+            debt::{Debt, DebtPayment, Installment},
+        },
+        querier::{
+            debt::{add_payment, delete, delete_payment, get_by_id, save},
+            record,
+        },
+    },
+    utils::{serve_empty, serve_full},
+};
+use chrono::NaiveDate;
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, Request, Response, StatusCode};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use ulid::Ulid;
+
+static COUNTERPARTY_MISSING: &[u8] = b"missing field: counterparty";
+static PRINCIPAL_MISSING: &[u8] = b"missing field: principal";
+static PRINCIPAL_ZERO: &[u8] = b"principal must be greater than zero";
+static FIRST_DUE_MISSING: &[u8] = b"missing field: first_due, format: YYYY-MM-DD";
+static DEBT_MISSING: &[u8] = b"missing field: debt_id";
+static RECORD_MISSING: &[u8] = b"missing field: record_id";
+static RECORD_NOT_FOUND: &[u8] = b"record not found";
+static PAYMENT_LINKED: &[u8] = b"this record is already a payment of the debt";
+
+pub async fn create_debt(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let counterparty = if let Some(e) = params.get("counterparty") {
+        e
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(COUNTERPARTY_MISSING))
+            .unwrap());
+    };
+    let principal: f32 = match params.get("principal").map(|v| v.parse::<f32>()) {
+        Some(Ok(v)) => v,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(PRINCIPAL_MISSING))
+                .unwrap())
+        }
+    };
+    if principal <= 0.0 {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(PRINCIPAL_ZERO))
+            .unwrap());
+    }
+    let book = if let Some(e) = params.get("book_id") {
+        e
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_empty())
+            .unwrap());
+    };
+    let book_id = Ulid::from_string(book).unwrap();
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let notes = params.get("notes").map_or("", |v| v.as_str());
+    let is_receivable = params.get("direction").is_some_and(|v| v == "receivable");
+    let new_debt = Debt::new(counterparty, notes, principal, is_receivable, book_id);
+
+    // the schedule is optional, an empty or zero count means no installments
+    let count = params
+        .get("installments")
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(0);
+    let installments = if count > 0 {
+        let first_due = match params
+            .get("first_due")
+            .map(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d"))
+        {
+            Some(Ok(v)) => v,
+            _ => {
+                return Ok(Response::builder()
+                    .status(StatusCode::UNPROCESSABLE_ENTITY)
+                    .body(serve_full(FIRST_DUE_MISSING))
+                    .unwrap())
+            }
+        };
+        Installment::schedule(new_debt.id, principal, count, first_due)
+    } else {
+        Vec::new()
+    };
+    match save(&pool, new_debt, installments).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .header("HX-Trigger", "createDebtSuccess")
+            .body(serve_full("Success create a debt"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+pub async fn delete_debt(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let debt_id = match params.get("debt_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(DEBT_MISSING))
+                .unwrap())
+        }
+    };
+    match get_by_id(debt_id, &pool).await {
+        Some(d) => {
+            if let Err(res) = role_guard(&pool, account_id, d.book_id, Role::can_organize).await {
+                return Ok(res);
+            }
+        }
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(serve_empty())
+                .unwrap())
+        }
+    }
+    match delete(&pool, debt_id).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "debtChangeSuccess")
+            .body(serve_full("Success delete a debt"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+// a payment links an existing record of the same book to the debt
+pub async fn create_debt_payment(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let debt = match params.get("debt_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => get_by_id(id, &pool).await,
+        _ => None,
+    };
+    let debt = if let Some(d) = debt {
+        d
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(DEBT_MISSING))
+            .unwrap());
+    };
+    if let Err(res) = role_guard(&pool, account_id, debt.book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let record_id = match params.get("record_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(RECORD_MISSING))
+                .unwrap())
+        }
+    };
+    match record::get_by_id(record_id, pool.clone()).await {
+        Some(r) if r.book_id == debt.book_id => {}
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(RECORD_NOT_FOUND))
+                .unwrap())
+        }
+    }
+    match add_payment(&pool, DebtPayment::new(debt.id, record_id)).await {
+        Ok(true) => Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .header("HX-Trigger", "debtChangeSuccess")
+            .body(serve_full("Success add a payment"))
+            .unwrap()),
+        Ok(false) => Ok(Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(serve_full(PAYMENT_LINKED))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+pub async fn delete_debt_payment(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let debt_id = match params.get("debt_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(DEBT_MISSING))
+                .unwrap())
+        }
+    };
+    match get_by_id(debt_id, &pool).await {
+        Some(d) => {
+            if let Err(res) = role_guard(&pool, account_id, d.book_id, Role::can_organize).await {
+                return Ok(res);
+            }
+        }
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(serve_empty())
+                .unwrap())
+        }
+    }
+    let payment = if let Some(e) = params.get("payment_id") {
+        e
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_empty())
+            .unwrap());
+    };
+    let payment_id = Ulid::from_string(payment).unwrap();
+    match delete_payment(&pool, debt_id, payment_id).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "debtChangeSuccess")
+            .body(serve_full("Success delete a payment"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
@@ -0,0 +1,77 @@
+use super::get_book_default_id;
+use crate::{
+    app::{events::subscribe, web::common::HandlerResult},
+    database::querier::book::get_role,
+    utils::{serve_full, serve_stream},
+};
+use hyper::{
+    body::{Bytes, Incoming},
+    header::{CACHE_CONTROL, CONTENT_TYPE},
+    Request, Response, StatusCode,
+};
+use sqlx::PgPool;
+use tokio::{
+    sync::{broadcast::error::RecvError, mpsc},
+    time::{interval, Duration},
+};
+use ulid::Ulid;
+
+static EVENTS_UNAVAILABLE: &[u8] = b"live updates are not available right now";
+
+// idle streams send a comment this often so proxies keep them open
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+// server-sent events of the active book: `record`, `category` and `book`
+// events name what changed and pages fetch what they show again on them
+pub async fn stream_book_events(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let mut events = match subscribe(&pool).await {
+        Ok(r) => r,
+        Err(_) => {
+            return Ok(Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .body(serve_full(EVENTS_UNAVAILABLE))
+                .unwrap())
+        }
+    };
+    let (tx, rx) = mpsc::channel(16);
+    tokio::spawn(async move {
+        // the first tick is immediate, which also gets the stream going
+        let mut keep_alive = interval(KEEP_ALIVE);
+        loop {
+            let chunk = tokio::select! {
+                _ = tx.closed() => break,
+                _ = keep_alive.tick() => Bytes::from_static(b": keep-alive\n\n"),
+                event = events.recv() => match event {
+                    Ok(e) if e.book_id == book_id => {
+                        // members taken out of the book stop hearing about it
+                        if get_role(&pool, book_id, account_id).await.is_none() {
+                            break;
+                        }
+                        Bytes::from(format!("event: {}\ndata: {}\n\n", e.kind.as_str(), e.book_id))
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+            };
+            if tx.send(chunk).await.is_err() {
+                break;
+            }
+        }
+    });
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "text/event-stream")
+        .header(CACHE_CONTROL, "no-cache")
+        .body(serve_stream(rx))
+        .unwrap())
+}
@@ -0,0 +1,265 @@
+use crate::{
+    app::{middlewares::book::role_guard, web::common::HandlerResult},
+    database::{
+        model::{
+            book::Role,
+            goal::{Contribution, Goal},
+        },
+        querier::{
+            goal::{add_contribution, delete, delete_contribution, get_by_id, save},
+            record,
+        },
+    },
+    utils::{serve_empty, serve_full},
+};
+use chrono::NaiveDate;
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, Request, Response, StatusCode};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use ulid::Ulid;
+
+static NAME_MISSING: &[u8] = b"missing field: name";
+static AMOUNT_MISSING: &[u8] = b"missing field: amount";
+static AMOUNT_ZERO: &[u8] = b"amount must be greater than zero";
+static DATE_MISSING: &[u8] = b"missing field: target_date, format: YYYY-MM-DD";
+static GOAL_MISSING: &[u8] = b"missing field: goal_id";
+static RECORD_NOT_FOUND: &[u8] = b"record not found";
+static CONTRIBUTION_MISSING: &[u8] = b"missing field: contribution_id";
+static RECORD_LINKED: &[u8] = b"this record already counts towards the goal";
+
+pub async fn create_goal(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let name = if let Some(e) = params.get("name") {
+        e
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(NAME_MISSING))
+            .unwrap());
+    };
+    let target_amount: f32 = match params.get("target_amount").map(|v| v.parse::<f32>()) {
+        Some(Ok(v)) => v,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(AMOUNT_MISSING))
+                .unwrap())
+        }
+    };
+    if target_amount <= 0.0 {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(AMOUNT_ZERO))
+            .unwrap());
+    }
+    let target_date = match params
+        .get("target_date")
+        .map(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d"))
+    {
+        Some(Ok(v)) => v,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(DATE_MISSING))
+                .unwrap())
+        }
+    };
+    let book = if let Some(e) = params.get("book_id") {
+        e
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_empty())
+            .unwrap());
+    };
+    let book_id = Ulid::from_string(book).unwrap();
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let new_goal = Goal::new(name, target_amount, target_date, book_id);
+    match save(&pool, new_goal).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .header("HX-Trigger", "creategoalSuccess")
+            .body(serve_full("Success create a goal"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+pub async fn delete_goal(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let goal_id = match params.get("goal_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(GOAL_MISSING))
+                .unwrap())
+        }
+    };
+    let goal = match get_by_id(goal_id, &pool).await {
+        Some(g) => g,
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    if let Err(res) = role_guard(&pool, account_id, goal.book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    match delete(&pool, goal_id).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "goalChangeSuccess")
+            .body(serve_full("Success delete a goal"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+// a contribution either links an existing record (`record_id`) or is a manual
+// entry with its own `amount` and `notes`
+pub async fn create_goal_contribution(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let goal_id = match params.get("goal_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(GOAL_MISSING))
+                .unwrap())
+        }
+    };
+    let goal = match get_by_id(goal_id, &pool).await {
+        Some(g) => g,
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    if let Err(res) = role_guard(&pool, account_id, goal.book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let record_id = params
+        .get("record_id")
+        .filter(|v| !v.is_empty())
+        .and_then(|v| Ulid::from_string(v).ok());
+    let contribution = match record_id {
+        Some(id) => match record::get_by_id(id, pool.clone()).await {
+            Some(r) if r.book_id == goal.book_id => {
+                Contribution::new(&r.notes, r.amount, goal_id, Some(r.id))
+            }
+            _ => {
+                return Ok(Response::builder()
+                    .status(StatusCode::UNPROCESSABLE_ENTITY)
+                    .body(serve_full(RECORD_NOT_FOUND))
+                    .unwrap())
+            }
+        },
+        None => {
+            let amount: f32 = match params.get("amount").map(|v| v.parse::<f32>()) {
+                Some(Ok(v)) if v > 0.0 => v,
+                _ => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::UNPROCESSABLE_ENTITY)
+                        .body(serve_full(AMOUNT_ZERO))
+                        .unwrap())
+                }
+            };
+            let notes = params.get("notes").map_or("", |v| v.as_str());
+            Contribution::new(notes, amount, goal_id, None)
+        }
+    };
+    match add_contribution(&pool, contribution).await {
+        Ok(true) => Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .header("HX-Trigger", "goalChangeSuccess")
+            .body(serve_full("Success add a contribution"))
+            .unwrap()),
+        Ok(false) => Ok(Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(serve_full(RECORD_LINKED))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+pub async fn delete_goal_contribution(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let goal_id = match params.get("goal_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(GOAL_MISSING))
+                .unwrap())
+        }
+    };
+    let goal = match get_by_id(goal_id, &pool).await {
+        Some(g) => g,
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    if let Err(res) = role_guard(&pool, account_id, goal.book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let contribution_id = match params.get("contribution_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(CONTRIBUTION_MISSING))
+                .unwrap())
+        }
+    };
+    match delete_contribution(&pool, goal_id, contribution_id).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "goalChangeSuccess")
+            .body(serve_full("Success delete a contribution"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
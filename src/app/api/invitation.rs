@@ -0,0 +1,77 @@
+use crate::{
+    app::web::common::HandlerResult,
+    database::{
+        model::invitation::InvitationStatus,
+        querier::invitation::{get_for_account, respond},
+    },
+    utils::{serve_empty, serve_full},
+};
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, Request, Response, StatusCode};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use ulid::Ulid;
+
+static TOKEN_MISSING: &[u8] = b"missing field: token";
+
+// answers an invitation sent to the email of the account; unknown, expired or
+// already answered tokens are not found
+async fn answer_invitation(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+    status: InvitationStatus,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let token = if let Some(e) = params.get("token") {
+        e
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(TOKEN_MISSING))
+            .unwrap());
+    };
+    let invitation = match get_for_account(token, account_id, &pool).await {
+        Some(i) => i,
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    match respond(&pool, &invitation, account_id, status).await {
+        Ok(true) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "invitationChangeSuccess")
+            .body(serve_full(format!("Invitation {}", status.as_str())))
+            .unwrap()),
+        Ok(false) => Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(serve_empty())
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+pub async fn accept_invitation(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    answer_invitation(req, pool, account_id, InvitationStatus::Accepted).await
+}
+
+pub async fn decline_invitation(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    answer_invitation(req, pool, account_id, InvitationStatus::Declined).await
+}
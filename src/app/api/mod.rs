@@ -1,6 +1,7 @@
-use cookie::Cookie;
+use cookie::{time::Duration, Cookie};
 use hyper::{body::Incoming, header::COOKIE, HeaderMap, Request};
 use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
 use ulid::Ulid;
 
 use crate::database::model::session::Session;
@@ -8,8 +9,17 @@ use crate::database::model::session::Session;
 pub mod account;
 pub mod book;
 pub mod category;
+pub mod debt;
+pub mod event;
+pub mod goal;
+pub mod invitation;
+pub mod payee;
 pub mod record;
+pub mod recurring;
+pub mod report;
+pub mod rule;
 pub mod session;
+pub mod split;
 
 pub async fn get_session_account_id(req: &Request<Incoming>, pool: &PgPool) -> Option<Ulid> {
     let headers = req.headers();
@@ -45,13 +55,34 @@ pub async fn get_session_account_id(req: &Request<Incoming>, pool: &PgPool) -> O
     }
 }
 
+// the book of the `book` cookie, none when it is missing or not a book id;
+// behind `book_middleware` it is always a book the account belongs to
 pub async fn get_book_default_id(h: &HeaderMap) -> Option<Ulid> {
-    let cookies = h.get(COOKIE).unwrap();
-    for cookie in Cookie::split_parse(cookies.to_str().unwrap()) {
-        let cookie = cookie.unwrap();
+    let cookies = h.get(COOKIE)?.to_str().ok()?;
+    for cookie in Cookie::split_parse(cookies).flatten() {
         if cookie.name() == "book" {
-            return Some(Ulid::from_string(cookie.value()).unwrap());
+            return Ulid::from_string(cookie.value()).ok();
         }
     }
     None
 }
+
+// the `Set-Cookie` value making the book the active one in the browser
+pub fn book_cookie(book_id: Ulid) -> String {
+    let mut c = Cookie::new("book", book_id.to_string());
+    c.set_max_age(Duration::days(30 * 12));
+    c.set_path("/");
+    c.to_string()
+}
+
+static VERSION_MISSING: &[u8] = b"missing field: version";
+static VERSION_INVALID: &[u8] = b"version must be a number";
+
+// the `version` an edit form was opened with, an edit without one could
+// overwrite a change it never saw
+pub fn version_from_params(params: &HashMap<String, String>) -> Result<i32, &'static [u8]> {
+    match params.get("version") {
+        Some(v) => v.trim().parse::<i32>().map_err(|_| VERSION_INVALID),
+        None => Err(VERSION_MISSING),
+    }
+}
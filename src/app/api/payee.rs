@@ -0,0 +1,85 @@
+use super::get_book_default_id;
+use crate::{
+    app::{middlewares::book::role_guard, web::common::HandlerResult},
+    database::{
+        model::book::Role,
+        querier::{
+            category,
+            payee::{get_by_id, set_default_category},
+        },
+    },
+    utils::{serve_empty, serve_full},
+};
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, Request, Response, StatusCode};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use ulid::Ulid;
+
+static PAYEE_MISSING: &[u8] = b"missing field: payee_id";
+static CAT_NOT_FOUND: &[u8] = b"category not found in this book";
+
+// sets the default category of a payee, an empty `category` clears it
+pub async fn edit_payee(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let payee_id = match params.get("payee_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(PAYEE_MISSING))
+                .unwrap())
+        }
+    };
+    match get_by_id(payee_id, &pool).await {
+        Some(p) if p.book_id == book_id => {}
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(serve_empty())
+                .unwrap())
+        }
+    }
+    let category_id = match params.get("category").filter(|v| !v.is_empty()) {
+        Some(v) => match Ulid::from_string(v) {
+            Ok(id) => match category::get_by_id(id, pool.clone()).await {
+                Some(c) if c.book_id == book_id => Some(id),
+                _ => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::UNPROCESSABLE_ENTITY)
+                        .body(serve_full(CAT_NOT_FOUND))
+                        .unwrap())
+                }
+            },
+            Err(_) => {
+                return Ok(Response::builder()
+                    .status(StatusCode::UNPROCESSABLE_ENTITY)
+                    .body(serve_full(CAT_NOT_FOUND))
+                    .unwrap())
+            }
+        },
+        None => None,
+    };
+    match set_default_category(&pool, payee_id, category_id).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(serve_full("Saved"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
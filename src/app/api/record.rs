@@ -1,22 +1,62 @@
 use crate::{
-    app::web::common::HandlerResult,
+    app::{
+        api::{rule::category_from_rules, version_from_params},
+        middlewares::book::{forbidden, member_guard, role_guard},
+        web::{common::HandlerResult, templates::RecordConflictTemplate},
+    },
     database::{
-        model::record::Record,
-        querier::record::{delete, edit, save},
+        model::{book::Role, payee::Payee, record::Record},
+        querier::{
+            category,
+            payee::find_or_create,
+            record::{delete, edit, get_by_id, save},
+            split,
+        },
     },
-    utils::{serve_empty, serve_full},
+    utils::{format_rupiah, serve_empty, serve_full},
 };
+use askama::Template;
 use http_body_util::BodyExt;
-use hyper::{body::Incoming, Request, Response, StatusCode};
+use hyper::{body::Incoming, header::CONTENT_TYPE, Request, Response, StatusCode};
 use sqlx::PgPool;
+use sqlx_core::error::BoxDynError;
 use std::collections::HashMap;
 use ulid::Ulid;
 static NOTES_MISSING: &[u8] = b"missing field: notes";
 static AMOUNT_MISSING: &[u8] = b"missing field: amount";
 static CAT_MISSING: &[u8] = b"missing field: record";
 static AMOUNT_ZERO: &[u8] = b"amount cannot be zero";
+static NO_RULE_MATCHED: &[u8] = b"no rule matches these notes, choose a category";
+static CAT_NOT_FOUND: &[u8] = b"category not found in this book";
+
+// reads the optional `payee` name, creating the payee the first time it is used
+async fn payee_from_params(
+    params: &HashMap<String, String>,
+    pool: &PgPool,
+    book_id: Ulid,
+) -> Result<Option<Payee>, BoxDynError> {
+    match params
+        .get("payee")
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+    {
+        Some(name) => find_or_create(pool, book_id, name).await.map(Some),
+        None => Ok(None),
+    }
+}
 
-pub async fn create_record(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+// a record can only use a category of its own book
+async fn category_in_book(pool: &PgPool, category_id: Ulid, book_id: Ulid) -> bool {
+    category::get_by_id(category_id, pool.clone())
+        .await
+        .is_some_and(|c| c.book_id == book_id)
+}
+
+pub async fn create_record(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
     let body = req.collect().await?.to_bytes();
     let params = form_urlencoded::parse(body.as_ref())
         .into_owned()
@@ -53,22 +93,66 @@ pub async fn create_record(req: Request<Incoming>, pool: PgPool, _: Ulid) -> Han
             .unwrap());
     };
     let book_id = Ulid::from_string(book).unwrap().to_bytes();
-
-    let category_id_str = if let Some(e) = params.get("category") {
-        e
-    } else {
-        return Ok(Response::builder()
-            .status(StatusCode::UNPROCESSABLE_ENTITY)
-            .body(serve_full(CAT_MISSING))
-            .unwrap());
+    if let Err(res) = role_guard(
+        &pool,
+        account_id,
+        Ulid::from_bytes(book_id),
+        Role::can_add_records,
+    )
+    .await
+    {
+        return Ok(res);
+    }
+    let payee = match payee_from_params(&params, &pool, Ulid::from_bytes(book_id)).await {
+        Ok(p) => p,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err.to_string()))
+                .unwrap())
+        }
     };
-    let category_id = Ulid::from_string(category_id_str).unwrap().to_bytes();
-    let new_record = Record::new(
+    // without a category the default category of the payee is used, then the
+    // rules of the book pick one
+    let mut from_rule = false;
+    let category_id = match params.get("category").filter(|v| !v.is_empty()) {
+        Some(v) => {
+            let id = Ulid::from_string(v).unwrap();
+            if !category_in_book(&pool, id, Ulid::from_bytes(book_id)).await {
+                return Ok(Response::builder()
+                    .status(StatusCode::UNPROCESSABLE_ENTITY)
+                    .body(serve_full(CAT_NOT_FOUND))
+                    .unwrap());
+            }
+            id.to_bytes()
+        }
+        None => match payee.as_ref().and_then(|p| p.default_category_id) {
+            Some(id) => id.to_bytes(),
+            None => {
+                match category_from_rules(&pool, Ulid::from_bytes(book_id), notes, amount).await {
+                    Some(id) => {
+                        from_rule = true;
+                        id.to_bytes()
+                    }
+                    None => {
+                        return Ok(Response::builder()
+                            .status(StatusCode::UNPROCESSABLE_ENTITY)
+                            .body(serve_full(NO_RULE_MATCHED))
+                            .unwrap())
+                    }
+                }
+            }
+        },
+    };
+    let mut new_record = Record::new(
         notes,
         amount,
         Ulid::from_bytes(book_id),
         Ulid::from_bytes(category_id),
     );
+    new_record.payee_id = payee.map(|p| p.id);
+    new_record.created_by = Some(account_id);
+    new_record.category_from_rule = from_rule;
     match save(&pool, new_record).await {
         Ok(_) => Ok(Response::builder()
             .status(StatusCode::CREATED)
@@ -82,7 +166,34 @@ pub async fn create_record(req: Request<Incoming>, pool: PgPool, _: Ulid) -> Han
     }
 }
 
-pub async fn edit_record(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+// answers an edit made on an older version of the record with the record as
+// it is now, so the change can be checked and sent again
+async fn record_conflict(pool: &PgPool, record_id: Ulid) -> HandlerResult {
+    let current = match get_by_id(record_id, pool.clone()).await {
+        Some(r) => r,
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    let template = RecordConflictTemplate {
+        id: current.id.to_string(),
+        notes: current.notes,
+        payee: current.payee_name,
+        amount_in_rupiah: format_rupiah(current.amount),
+        category_name: current.category_name,
+        version: current.version,
+    };
+    Ok(Response::builder()
+        .status(StatusCode::CONFLICT)
+        .header(CONTENT_TYPE, "text/html")
+        .body(serve_full(template.render().expect("Should render markup")))
+        .unwrap())
+}
+
+pub async fn edit_record(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
     let body = req.collect().await?.to_bytes();
     let params = form_urlencoded::parse(body.as_ref())
         .into_owned()
@@ -128,20 +239,87 @@ pub async fn edit_record(req: Request<Incoming>, pool: PgPool, _: Ulid) -> Handl
             .unwrap());
     };
     let category_id = Ulid::from_string(category_id_str).unwrap().to_bytes();
+    let r = match get_by_id(record_id.into(), pool.clone()).await {
+        Some(r) => r,
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_empty())
+                .unwrap())
+        }
+    };
+    match member_guard(&pool, account_id, r.book_id).await {
+        Ok(role) if role.can_change_record(r.created_by, account_id) => {}
+        Ok(_) => return Ok(forbidden()),
+        Err(res) => return Ok(res),
+    }
+    if !category_in_book(&pool, category_id.into(), r.book_id).await {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(CAT_NOT_FOUND))
+            .unwrap());
+    }
+    let version = match version_from_params(&params) {
+        Ok(v) => v,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap())
+        }
+    };
+    let payee = match payee_from_params(&params, &pool, r.book_id).await {
+        Ok(p) => p,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err.to_string()))
+                .unwrap())
+        }
+    };
+    // the shares of a split record follow its amount
+    let reshared = match split::get_by_record_id(r.id, &pool).await {
+        Some(s) if amount != r.amount => {
+            let current = split::get_shares(s.id, &pool).await;
+            match s.reshare(amount, &current) {
+                Ok(shares) => Some((s.id, shares)),
+                Err(err) => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::UNPROCESSABLE_ENTITY)
+                        .body(serve_full(err))
+                        .unwrap())
+                }
+            }
+        }
+        _ => None,
+    };
     match edit(
         &pool,
         notes.to_string(),
         amount,
         record_id.into(),
         category_id.into(),
+        payee.map(|p| p.id),
+        version,
     )
     .await
     {
-        Ok(_) => Ok(Response::builder()
-            .status(StatusCode::OK)
-            .header("HX-Trigger", "recordChangeSuccess")
-            .body(serve_full("Success change record"))
-            .unwrap()),
+        Ok(true) => {
+            if let Some((split_id, shares)) = reshared {
+                if let Err(err) = split::set_shares(&pool, split_id, shares).await {
+                    return Ok(Response::builder()
+                        .status(StatusCode::UNPROCESSABLE_ENTITY)
+                        .body(serve_full(err.to_string()))
+                        .unwrap());
+                }
+            }
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header("HX-Trigger", "recordChangeSuccess")
+                .body(serve_full("Success change record"))
+                .unwrap())
+        }
+        Ok(false) => record_conflict(&pool, r.id).await,
         Err(err) => Ok(Response::builder()
             .status(StatusCode::UNPROCESSABLE_ENTITY)
             .body(serve_full(err.to_string()))
@@ -149,7 +327,11 @@ pub async fn edit_record(req: Request<Incoming>, pool: PgPool, _: Ulid) -> Handl
     }
 }
 
-pub async fn delete_record(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+pub async fn delete_record(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
     let body = req.collect().await?.to_bytes();
     let params = form_urlencoded::parse(body.as_ref())
         .into_owned()
@@ -162,18 +344,29 @@ pub async fn delete_record(req: Request<Incoming>, pool: PgPool, _: Ulid) -> Han
             .body(serve_empty())
             .unwrap());
     };
-    let record_id = Ulid::from_string(record).unwrap().to_bytes();
-    match delete(&pool, record_id.into()).await {
+    let record_id = Ulid::from_string(record).unwrap();
+    match get_by_id(record_id, pool.clone()).await {
+        Some(r) => match member_guard(&pool, account_id, r.book_id).await {
+            Ok(role) if role.can_change_record(r.created_by, account_id) => {}
+            Ok(_) => return Ok(forbidden()),
+            Err(res) => return Ok(res),
+        },
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_empty())
+                .unwrap())
+        }
+    }
+    match delete(&pool, record_id).await {
         Ok(_) => Ok(Response::builder()
             .status(StatusCode::OK)
             .header("HX-Trigger", "recordChangeSuccess")
             .body(serve_full("Success delete a record"))
             .unwrap()),
-        Err(err) => {
-            Ok(Response::builder()
-                .status(StatusCode::UNPROCESSABLE_ENTITY)
-                .body(serve_full(err.to_string()))
-                .unwrap())
-        }
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
     }
 }
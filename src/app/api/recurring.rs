@@ -0,0 +1,152 @@
+use crate::{
+    app::{middlewares::book::role_guard, web::common::HandlerResult},
+    database::{
+        model::{book::Role, recurring::Recurring},
+        querier::{
+            category,
+            recurring::{delete, get_by_id, save},
+        },
+    },
+    utils::{serve_empty, serve_full},
+};
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, Request, Response, StatusCode};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use ulid::Ulid;
+
+static NOTES_MISSING: &[u8] = b"missing field: notes";
+static AMOUNT_MISSING: &[u8] = b"missing field: amount";
+static AMOUNT_ZERO: &[u8] = b"amount cannot be zero";
+static DAY_INVALID: &[u8] = b"day of month must be between 1 and 31";
+static CAT_MISSING: &[u8] = b"missing field: category";
+static CAT_NOT_FOUND: &[u8] = b"category not found in this book";
+
+pub async fn create_recurring(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let notes = if let Some(e) = params.get("notes") {
+        e
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(NOTES_MISSING))
+            .unwrap());
+    };
+    let amount: f32 = match params.get("amount").map(|v| v.parse::<f32>()) {
+        Some(Ok(v)) => v,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(AMOUNT_MISSING))
+                .unwrap())
+        }
+    };
+    if amount == 0.0 {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(AMOUNT_ZERO))
+            .unwrap());
+    }
+    let day_of_month: i32 = match params.get("day_of_month").map(|v| v.parse::<i32>()) {
+        Some(Ok(v)) if (1..=31).contains(&v) => v,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(DAY_INVALID))
+                .unwrap())
+        }
+    };
+    let book = if let Some(e) = params.get("book_id") {
+        e
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_empty())
+            .unwrap());
+    };
+    let book_id = Ulid::from_string(book).unwrap();
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let category_id = match params.get("category").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(CAT_MISSING))
+                .unwrap())
+        }
+    };
+    match category::get_by_id(category_id, pool.clone()).await {
+        Some(c) if c.book_id == book_id => {}
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(CAT_NOT_FOUND))
+                .unwrap())
+        }
+    }
+    let new_recurring = Recurring::new(notes, amount, day_of_month, book_id, category_id);
+    match save(&pool, new_recurring).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .header("HX-Trigger", "createrecurringSuccess")
+            .body(serve_full("Success create a recurring item"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+pub async fn delete_recurring(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let recurring = if let Some(e) = params.get("recurring_id") {
+        e
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_empty())
+            .unwrap());
+    };
+    let recurring_id = Ulid::from_string(recurring).unwrap();
+    match get_by_id(recurring_id, &pool).await {
+        Some(r) => {
+            if let Err(res) = role_guard(&pool, account_id, r.book_id, Role::can_organize).await {
+                return Ok(res);
+            }
+        }
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(serve_empty())
+                .unwrap())
+        }
+    }
+    match delete(&pool, recurring_id).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "recurringChangeSuccess")
+            .body(serve_full("Success delete a recurring item"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
@@ -0,0 +1,271 @@
+use super::get_book_default_id;
+use crate::{
+    app::web::common::HandlerResult,
+    database::{
+        model::{
+            book::BookSettings,
+            forecast::Forecast,
+            record::RecordJson,
+            report::{BookTotal, Bucket, DateRange, Overview, Period, Report, Trend},
+        },
+        querier::{
+            book::{get_by_account_id, get_default, get_settings},
+            record::{fetch_by_account, fetch_by_category},
+            recurring::get_by_book_id,
+            report::{balance, category_bucket_totals, category_totals, rolled_up_category_totals},
+        },
+    },
+    utils::{parse_query, serve_full},
+};
+use chrono::NaiveDate;
+use hyper::{body::Incoming, header::CONTENT_TYPE, Request, Response, StatusCode};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use ulid::Ulid;
+
+static CAT_MISSING: &[u8] = b"missing field: category_id";
+
+fn parse_date(params: &HashMap<String, String>, key: &str) -> Option<NaiveDate> {
+    params
+        .get(key)
+        .and_then(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d").ok())
+}
+
+// reads `period`, `date`, `from` and `to` query params into a date range,
+// defaulting to the book's current month
+pub fn range_from_params(params: &HashMap<String, String>, settings: BookSettings) -> DateRange {
+    let today = settings.today();
+    let period = Period::parse(params.get("period").map_or("month", |v| v.as_str()));
+    match period {
+        Period::Custom => {
+            let from = parse_date(params, "from").unwrap_or(today);
+            let to = parse_date(params, "to").unwrap_or(today);
+            DateRange::custom(from, to, settings)
+        }
+        _ => DateRange::new(
+            period,
+            parse_date(params, "date").unwrap_or(today),
+            settings,
+        ),
+    }
+}
+
+// reads `bucket` and `count` query params, defaulting to the last 6 months
+// or the last 3 years
+pub fn trend_from_params(
+    params: &HashMap<String, String>,
+    settings: &BookSettings,
+) -> (Bucket, Vec<NaiveDate>) {
+    let today = settings.today();
+    let bucket = Bucket::parse(params.get("bucket").map_or("month", |v| v.as_str()));
+    let default_count = match bucket {
+        Bucket::Month => 6,
+        Bucket::Year => 3,
+    };
+    let count = params
+        .get("count")
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(default_count)
+        .clamp(2, 24);
+    (bucket, bucket.last(today, count, settings.month_start_day))
+}
+
+pub async fn load_trend(
+    pool: &PgPool,
+    book_id: Ulid,
+    settings: &BookSettings,
+    bucket: Bucket,
+    buckets: Vec<NaiveDate>,
+) -> Trend {
+    let (from, to) = bucket.span(&buckets, settings);
+    let rows = category_bucket_totals(pool, book_id, bucket, settings, from, to).await;
+    Trend::new(bucket, buckets, rows)
+}
+
+// number of past full months used to estimate a category's monthly amount
+const FORECAST_HISTORY_MONTHS: u32 = 3;
+
+pub fn forecast_months_from_params(params: &HashMap<String, String>) -> u32 {
+    params
+        .get("months")
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(3)
+        .clamp(3, 6)
+}
+
+// forecast months start on the book's month start day like its reports do
+pub async fn load_forecast(
+    pool: &PgPool,
+    book_id: Ulid,
+    settings: &BookSettings,
+    months: u32,
+) -> Forecast {
+    let now = chrono::offset::Utc::now();
+    let today = settings.local_date(now);
+    let current = Bucket::Month.start_of(today, settings.month_start_day);
+    let upcoming: Vec<NaiveDate> = (1..=months as i32)
+        .map(|i| Bucket::Month.shift(current, i))
+        .collect();
+    let history_start = Bucket::Month.shift(current, -(FORECAST_HISTORY_MONTHS as i32));
+    let history = category_totals(
+        pool,
+        book_id,
+        settings.midnight(history_start),
+        settings.midnight(current),
+    )
+    .await;
+    let recurrings = get_by_book_id(book_id, pool).await;
+    let opening = balance(pool, book_id, now).await;
+    Forecast::new(
+        opening,
+        &upcoming,
+        &recurrings,
+        &history,
+        FORECAST_HISTORY_MONTHS,
+    )
+}
+
+// number of records in the merged feed of the overview
+const OVERVIEW_RECORDS: i32 = 20;
+
+// totals of every book of the account over the period of the query params,
+// counted once in the timezone and month start day of the account's default
+// book so every book covers the same dates
+pub async fn load_overview(
+    pool: &PgPool,
+    account_id: Ulid,
+    params: &HashMap<String, String>,
+) -> Overview {
+    let settings = match get_default(pool, account_id).await {
+        Some(id) => get_settings(pool, id).await,
+        None => BookSettings::default(),
+    };
+    let range = range_from_params(params, settings);
+    let mut books: Vec<BookTotal> = Vec::new();
+    for book in get_by_account_id(account_id, pool.clone()).await {
+        let report = Report::new(
+            range,
+            category_totals(pool, book.id, range.start(), range.end()).await,
+        );
+        books.push(BookTotal {
+            book_id: book.id,
+            settings: book.settings(),
+            name: book.name,
+            total_income: report.total_income,
+            total_expense: report.total_expense,
+        });
+    }
+    let records = fetch_by_account(account_id, OVERVIEW_RECORDS, pool).await;
+    Overview {
+        range,
+        books,
+        records,
+    }
+}
+
+pub fn range_query(range: &DateRange) -> String {
+    format!(
+        "period={}&date={}&from={}&to={}",
+        range.period.as_str(),
+        range.from,
+        range.from,
+        range.last_day()
+    )
+}
+
+pub async fn get_report(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let params = parse_query(req.uri().query());
+    let settings = get_settings(&pool, book_id).await;
+    let range = range_from_params(&params, settings);
+    let totals = rolled_up_category_totals(&pool, book_id, range.start(), range.end()).await;
+    let report = Report::new(range, totals);
+    let json = serde_json::to_string(&report.to_json()).unwrap();
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .body(serve_full(json))
+        .unwrap())
+}
+
+pub async fn get_report_records(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let params = parse_query(req.uri().query());
+    let category_id = match params.get("category_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(CAT_MISSING))
+                .unwrap())
+        }
+    };
+    let range = range_from_params(&params, get_settings(&pool, book_id).await);
+    let records = fetch_by_category(book_id, category_id, range.start(), range.end(), &pool).await;
+    let datas: Vec<RecordJson> = records.iter().map(|r| r.to_json()).collect();
+    let json = serde_json::to_string(&datas).unwrap();
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .body(serve_full(json))
+        .unwrap())
+}
+
+pub async fn get_trend(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let params = parse_query(req.uri().query());
+    let settings = get_settings(&pool, book_id).await;
+    let (bucket, buckets) = trend_from_params(&params, &settings);
+    let trend = load_trend(&pool, book_id, &settings, bucket, buckets).await;
+    let json = serde_json::to_string(&trend.to_json()).unwrap();
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .body(serve_full(json))
+        .unwrap())
+}
+
+pub async fn get_forecast(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let params = parse_query(req.uri().query());
+    let settings = get_settings(&pool, book_id).await;
+    let months = forecast_months_from_params(&params);
+    let forecast = load_forecast(&pool, book_id, &settings, months).await;
+    let json = serde_json::to_string(&forecast).unwrap();
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .body(serve_full(json))
+        .unwrap())
+}
+
+pub async fn get_overview(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let params = parse_query(req.uri().query());
+    let overview = load_overview(&pool, account_id, &params).await;
+    let json = serde_json::to_string(&overview.to_json()).unwrap();
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .body(serve_full(json))
+        .unwrap())
+}
@@ -0,0 +1,198 @@
+use super::{get_book_default_id, report::range_from_params};
+use crate::{
+    app::{middlewares::book::role_guard, web::common::HandlerResult},
+    database::{
+        model::{
+            book::Role,
+            rule::{first_match, Matcher, Rule, RuleKind},
+        },
+        querier::{
+            book::get_settings,
+            category,
+            rule::{apply, delete, get_by_book_id, get_by_id, save},
+        },
+    },
+    utils::{serve_empty, serve_full},
+};
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, Request, Response, StatusCode};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use ulid::Ulid;
+
+static CAT_MISSING: &[u8] = b"missing field: category";
+static CAT_NOT_FOUND: &[u8] = b"category not found in this book";
+static AMOUNT_INVALID: &[u8] = b"min_amount and max_amount must be numbers";
+static RULE_MISSING: &[u8] = b"missing field: rule_id";
+
+// reads an optional amount, an empty value means no bound
+fn amount_from_params(params: &HashMap<String, String>, key: &str) -> Result<Option<f32>, ()> {
+    match params.get(key).filter(|v| !v.is_empty()) {
+        Some(v) => v.parse::<f32>().map(Some).map_err(|_| ()),
+        None => Ok(None),
+    }
+}
+
+// the category of the first rule of the book matching the record, if any
+pub async fn category_from_rules(
+    pool: &PgPool,
+    book_id: Ulid,
+    notes: &str,
+    amount: f32,
+) -> Option<Ulid> {
+    let rules = get_by_book_id(book_id, pool).await;
+    let matchers: Vec<Matcher> = rules.iter().map(Rule::matcher).collect();
+    first_match(&matchers, notes, amount).map(|r| r.category_id)
+}
+
+pub async fn create_rule(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let book = if let Some(e) = params.get("book_id") {
+        e
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_empty())
+            .unwrap());
+    };
+    let book_id = Ulid::from_string(book).unwrap();
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let category_id = match params.get("category").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(CAT_MISSING))
+                .unwrap())
+        }
+    };
+    match category::get_by_id(category_id, pool.clone()).await {
+        Some(c) if c.book_id == book_id => {}
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(CAT_NOT_FOUND))
+                .unwrap())
+        }
+    }
+    let (min_amount, max_amount) = match (
+        amount_from_params(&params, "min_amount"),
+        amount_from_params(&params, "max_amount"),
+    ) {
+        (Ok(min), Ok(max)) => (min, max),
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(AMOUNT_INVALID))
+                .unwrap())
+        }
+    };
+    let kind = RuleKind::parse(params.get("kind").map_or("contains", |v| v.as_str()));
+    let pattern = params.get("pattern").map_or("", |v| v.as_str());
+    let new_rule = match Rule::new(kind, pattern, min_amount, max_amount, book_id, category_id) {
+        Ok(r) => r,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap())
+        }
+    };
+    match save(&pool, new_rule).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .header("HX-Trigger", "createRuleSuccess")
+            .body(serve_full("Success create a rule"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+pub async fn delete_rule(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let rule_id = match params.get("rule_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(RULE_MISSING))
+                .unwrap())
+        }
+    };
+    match get_by_id(rule_id, &pool).await {
+        Some(r) if r.book_id == book_id => {}
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(serve_empty())
+                .unwrap())
+        }
+    }
+    match delete(&pool, rule_id).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "ruleChangeSuccess")
+            .body(serve_full("Success delete a rule"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+// re-runs the rules of the current book over the records of the `from` and
+// `to` range whose category a rule picked, and lists the records it moved
+pub async fn apply_rules(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_organize).await {
+        return Ok(res);
+    }
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let range = range_from_params(&params, get_settings(&pool, book_id).await);
+    let rules = get_by_book_id(book_id, &pool).await;
+    match apply(&pool, book_id, &rules, range.start(), range.end()).await {
+        Ok(changed) => {
+            let mut message = format!("Rules applied, {} records changed category", changed.len());
+            for c in changed {
+                message.push_str(&format!("\n{} → {}", c.notes, c.category_name));
+            }
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .body(serve_full(message))
+                .unwrap())
+        }
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
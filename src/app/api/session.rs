@@ -1,6 +1,8 @@
+use crate::app::api::book_cookie;
 use crate::app::web::common::HandlerResult;
 use crate::database::model::account::Account;
 use crate::database::model::session::Session;
+use crate::database::querier::book::get_default;
 use crate::utils::serve_full;
 use cookie::time::Duration;
 use cookie::Cookie;
@@ -86,12 +88,14 @@ pub async fn login_account(req: Request<Incoming>, pool: PgPool) -> HandlerResul
             {
                 Ok(v) => {
                     let session_token = Session::from_row(&v).unwrap().token;
-                    return Ok(Response::builder()
+                    let mut res = Response::builder()
                         .status(StatusCode::OK)
                         .header("HX-Trigger", "loginSuccess")
-                        .header(SET_COOKIE, session_token.to_string())
-                        .body(serve_full("Success login"))
-                        .unwrap());
+                        .header(SET_COOKIE, session_token.to_string());
+                    if let Some(id) = get_default(&pool, acc.id).await {
+                        res = res.header(SET_COOKIE, book_cookie(id));
+                    }
+                    return Ok(res.body(serve_full("Success login")).unwrap());
                 }
                 Err(err) => {
                     if !matches!(err, RowNotFound) {
@@ -137,12 +141,15 @@ pub async fn login_account(req: Request<Incoming>, pool: PgPool) -> HandlerResul
                     c.set_path("/");
                     c.set_secure(true);
 
-                    Ok(Response::builder()
+                    let mut res = Response::builder()
                         .status(StatusCode::OK)
                         .header("HX-Trigger", "loginSuccess")
-                        .header(SET_COOKIE, c.to_string())
-                        .body(serve_full("Success login"))
-                        .unwrap())
+                        .header(SET_COOKIE, c.to_string());
+                    // the book picked on another device comes along
+                    if let Some(id) = get_default(&pool, acc.id).await {
+                        res = res.header(SET_COOKIE, book_cookie(id));
+                    }
+                    Ok(res.body(serve_full("Success login")).unwrap())
                 }
                 Err(err) => {
                     Ok(Response::builder()
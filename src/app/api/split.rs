@@ -0,0 +1,224 @@
+use crate::{
+    app::{
+        middlewares::book::{forbidden, member_guard, role_guard},
+        web::common::HandlerResult,
+    },
+    database::{
+        model::{
+            book::Role,
+            split::{Settlement, Split, SplitMethod},
+        },
+        querier::{
+            book::get_members,
+            record,
+            split::{delete, save, save_settlement},
+        },
+    },
+    utils::{serve_empty, serve_full},
+};
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, Request, Response, StatusCode};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use ulid::Ulid;
+
+static RECORD_MISSING: &[u8] = b"missing field: record_id";
+static PAID_BY_MISSING: &[u8] = b"missing field: paid_by";
+static NOT_A_MEMBER: &[u8] = b"every member of a split must belong to the book";
+static ACCOUNT_MISSING: &[u8] = b"missing field: from_account_id or to_account_id";
+static SAME_ACCOUNT: &[u8] = b"a member cannot reimburse themselves";
+static AMOUNT_ZERO: &[u8] = b"amount must be greater than zero";
+
+// included members are sent as `member_<account id>` with their percentage or
+// exact share in `value_<account id>`
+pub async fn save_split(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let r = match params.get("record_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => record::get_by_id(id, pool.clone()).await,
+        _ => None,
+    };
+    let r = if let Some(r) = r {
+        r
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(RECORD_MISSING))
+            .unwrap());
+    };
+    // splitting a record changes it, so it takes the same role as editing it
+    match member_guard(&pool, account_id, r.book_id).await {
+        Ok(role) if role.can_change_record(r.created_by, account_id) => {}
+        Ok(_) => return Ok(forbidden()),
+        Err(res) => return Ok(res),
+    }
+    let paid_by = match params.get("paid_by").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(PAID_BY_MISSING))
+                .unwrap())
+        }
+    };
+    let members: Vec<Ulid> = get_members(r.book_id, &pool)
+        .await
+        .iter()
+        .map(|m| m.account_id)
+        .collect();
+    let mut values: Vec<(Ulid, f32)> = Vec::new();
+    for (key, _) in params.iter() {
+        let id = match key
+            .strip_prefix("member_")
+            .and_then(|v| Ulid::from_string(v).ok())
+        {
+            Some(id) => id,
+            None => continue,
+        };
+        let value = params
+            .get(&format!("value_{}", id))
+            .and_then(|v| v.parse::<f32>().ok())
+            .unwrap_or(0.0);
+        values.push((id, value));
+    }
+    values.sort_by_key(|(id, _)| *id);
+    if !members.contains(&paid_by) || values.iter().any(|(id, _)| !members.contains(id)) {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(NOT_A_MEMBER))
+            .unwrap());
+    }
+    let method = SplitMethod::parse(params.get("method").map_or("equal", |v| v.as_str()));
+    let split = Split::new(r.id, paid_by, method);
+    let shares = match split.shares(r.amount, &values) {
+        Ok(v) => v,
+        Err(err) => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(err))
+                .unwrap())
+        }
+    };
+    match save(&pool, split, shares).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .header("HX-Trigger", "splitChangeSuccess")
+            .body(serve_full("Success split a record"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+pub async fn delete_split(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let record_id = match params.get("record_id").map(|v| Ulid::from_string(v)) {
+        Some(Ok(id)) => id,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(RECORD_MISSING))
+                .unwrap())
+        }
+    };
+    match record::get_by_id(record_id, pool.clone()).await {
+        Some(r) => match member_guard(&pool, account_id, r.book_id).await {
+            Ok(role) if role.can_change_record(r.created_by, account_id) => {}
+            Ok(_) => return Ok(forbidden()),
+            Err(res) => return Ok(res),
+        },
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(RECORD_MISSING))
+                .unwrap())
+        }
+    }
+    match delete(&pool, record_id).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("HX-Trigger", "splitChangeSuccess")
+            .body(serve_full("Success remove a split"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
+
+// records a reimbursement, usually one of the suggested settle-up transfers
+pub async fn settle_up(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let body = req.collect().await?.to_bytes();
+    let params = form_urlencoded::parse(body.as_ref())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+    let book = if let Some(e) = params.get("book_id") {
+        e
+    } else {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_empty())
+            .unwrap());
+    };
+    let book_id = Ulid::from_string(book).unwrap();
+    if let Err(res) = role_guard(&pool, account_id, book_id, Role::can_add_records).await {
+        return Ok(res);
+    }
+    let (from, to) = match (
+        params.get("from_account_id").map(|v| Ulid::from_string(v)),
+        params.get("to_account_id").map(|v| Ulid::from_string(v)),
+    ) {
+        (Some(Ok(from)), Some(Ok(to))) => (from, to),
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(ACCOUNT_MISSING))
+                .unwrap())
+        }
+    };
+    if from == to {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(SAME_ACCOUNT))
+            .unwrap());
+    }
+    let amount: f32 = match params.get("amount").map(|v| v.parse::<f32>()) {
+        Some(Ok(v)) if v > 0.0 => v,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::UNPROCESSABLE_ENTITY)
+                .body(serve_full(AMOUNT_ZERO))
+                .unwrap())
+        }
+    };
+    let members: Vec<Ulid> = get_members(book_id, &pool)
+        .await
+        .iter()
+        .map(|m| m.account_id)
+        .collect();
+    if !members.contains(&from) || !members.contains(&to) {
+        return Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(NOT_A_MEMBER))
+            .unwrap());
+    }
+    match save_settlement(&pool, Settlement::new(book_id, from, to, amount)).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .header("HX-Trigger", "splitChangeSuccess")
+            .body(serve_full("Success record a settlement"))
+            .unwrap()),
+        Err(err) => Ok(Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(serve_full(err.to_string()))
+            .unwrap()),
+    }
+}
@@ -0,0 +1,102 @@
+use log::error;
+use serde::Deserialize;
+use sqlx::{postgres::PgListener, PgPool};
+use tokio::{
+    sync::{broadcast, Mutex},
+    task::JoinHandle,
+    time::{sleep, Duration},
+};
+use ulid::Ulid;
+
+// postgres channel the `notify_book_change` trigger writes to
+pub const CHANNEL: &str = "book_changes";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EventKind {
+    Record,
+    Category,
+    Book,
+}
+
+impl EventKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "record" => Some(EventKind::Record),
+            "category" => Some(EventKind::Category),
+            "book" => Some(EventKind::Book),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Record => "record",
+            EventKind::Category => "category",
+            EventKind::Book => "book",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BookEvent {
+    pub book_id: Ulid,
+    pub kind: EventKind,
+}
+
+#[derive(Deserialize)]
+struct Payload {
+    book_id: String,
+    kind: String,
+}
+
+impl BookEvent {
+    // the trigger sends the book id as the hex of its bytes
+    pub fn parse(payload: &str) -> Option<Self> {
+        let payload: Payload = serde_json::from_str(payload).ok()?;
+        Some(Self {
+            book_id: Ulid(u128::from_str_radix(&payload.book_id, 16).ok()?),
+            kind: EventKind::parse(&payload.kind)?,
+        })
+    }
+}
+
+struct Hub {
+    sender: broadcast::Sender<BookEvent>,
+    task: JoinHandle<()>,
+}
+
+// one LISTEN connection per server, shared by every open event stream
+static HUB: Mutex<Option<Hub>> = Mutex::const_new(None);
+
+// events of every book, the caller picks the ones it may see; the hub is
+// started on first use and again whenever its task is gone
+pub async fn subscribe(pool: &PgPool) -> Result<broadcast::Receiver<BookEvent>, sqlx::Error> {
+    let mut hub = HUB.lock().await;
+    if let Some(h) = hub.as_ref().filter(|h| !h.task.is_finished()) {
+        return Ok(h.sender.subscribe());
+    }
+    // listen before handing out receivers so no change after this is missed
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(CHANNEL).await?;
+    let (sender, receiver) = broadcast::channel(256);
+    let task = tokio::spawn(forward(listener, sender.clone()));
+    *hub = Some(Hub { sender, task });
+    Ok(receiver)
+}
+
+// the listener reconnects by itself, errors here are only logged and retried
+async fn forward(mut listener: PgListener, sender: broadcast::Sender<BookEvent>) {
+    loop {
+        match listener.recv().await {
+            Ok(notification) => {
+                if let Some(event) = BookEvent::parse(notification.payload()) {
+                    let _ = sender.send(event);
+                }
+            }
+            Err(err) => {
+                error!("failed to receive book changes: {:?}", err);
+                sleep(Duration::from_secs(5)).await;
+            }
+        }
+    }
+}
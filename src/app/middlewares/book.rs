@@ -0,0 +1,128 @@
+use super::session::auth_middleware;
+use crate::{
+    app::{
+        api::{book_cookie, get_book_default_id},
+        web::common::HandlerResult,
+    },
+    database::{
+        model::book::Role,
+        querier::book::{get_default, get_role},
+    },
+    utils::{serve_empty, serve_full},
+};
+use cookie::Cookie;
+use http_body_util::combinators::BoxBody;
+use hyper::{
+    body::{Bytes, Incoming},
+    header::{HeaderValue, COOKIE, LOCATION, SET_COOKIE},
+    HeaderMap, Request, Response, StatusCode,
+};
+use sqlx::PgPool;
+use std::{convert::Infallible, future::Future};
+use ulid::Ulid;
+
+static ROLE_FORBIDDEN: &[u8] = b"your role in this book does not allow this";
+
+// answers 404 unless the account is a member of the book, so other accounts
+// cannot tell the book, or anything in it, exists; members get their role
+pub async fn member_guard(
+    pool: &PgPool,
+    account_id: Ulid,
+    book_id: Ulid,
+) -> Result<Role, Response<BoxBody<Bytes, Infallible>>> {
+    match get_role(pool, book_id, account_id).await {
+        Some(role) => Ok(role),
+        None => Err(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(serve_empty())
+            .unwrap()),
+    }
+}
+
+pub fn forbidden() -> Response<BoxBody<Bytes, Infallible>> {
+    Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .body(serve_full(ROLE_FORBIDDEN))
+        .unwrap()
+}
+
+// `member_guard` that also answers 403 to members whose role is not `allowed`
+pub async fn role_guard(
+    pool: &PgPool,
+    account_id: Ulid,
+    book_id: Ulid,
+    allowed: fn(&Role) -> bool,
+) -> Result<Role, Response<BoxBody<Bytes, Infallible>>> {
+    let role = member_guard(pool, account_id, book_id).await?;
+    if allowed(&role) {
+        Ok(role)
+    } else {
+        Err(forbidden())
+    }
+}
+
+// the book the account works in, the one of the `book` cookie when the account
+// belongs to it, otherwise its default book
+pub async fn active_book(pool: &PgPool, account_id: Ulid, headers: &HeaderMap) -> Option<Ulid> {
+    match get_book_default_id(headers).await {
+        Some(id) if get_role(pool, id, account_id).await.is_some() => Some(id),
+        _ => get_default(pool, account_id).await,
+    }
+}
+
+// the cookies of the request with `book` replaced by the given book
+fn with_book_cookie(headers: &HeaderMap, book_id: Ulid) -> HeaderValue {
+    let mut cookies: Vec<String> = headers
+        .get(COOKIE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            Cookie::split_parse(v)
+                .flatten()
+                .filter(|c| c.name() != "book")
+                .map(|c| format!("{}={}", c.name(), c.value()))
+                .collect()
+        })
+        .unwrap_or_default();
+    cookies.push(format!("book={}", book_id));
+    HeaderValue::from_str(&cookies.join("; ")).unwrap()
+}
+
+// `auth_middleware` for handlers working on the book of the `book` cookie;
+// when the cookie is missing, broken or names a book the account is not in,
+// the handler gets the default book instead and the browser is told to switch
+pub async fn book_middleware<F, Fut>(req: Request<Incoming>, pool: PgPool, next: F) -> HandlerResult
+where
+    F: Fn(Request<Incoming>, PgPool, Ulid) -> Fut + Copy,
+    Fut: Future<Output = HandlerResult>,
+{
+    let run = move |mut req: Request<Incoming>, pool: PgPool, account_id: Ulid| async move {
+        let cookie_book = get_book_default_id(req.headers()).await;
+        match active_book(&pool, account_id, req.headers()).await {
+            Some(id) if cookie_book == Some(id) => next(req, pool, account_id).await,
+            Some(id) => {
+                let cookies = with_book_cookie(req.headers(), id);
+                req.headers_mut().insert(COOKIE, cookies);
+                let mut res = next(req, pool, account_id).await?;
+                res.headers_mut()
+                    .append(SET_COOKIE, HeaderValue::from_str(&book_cookie(id)).unwrap());
+                Ok(res)
+            }
+            // the account has no book left to work in
+            None => {
+                if req.uri().path().starts_with("/api/") {
+                    Ok(Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(serve_empty())
+                        .unwrap())
+                } else {
+                    Ok(Response::builder()
+                        .status(StatusCode::TEMPORARY_REDIRECT)
+                        .header(LOCATION, "/book/create")
+                        .body(serve_empty())
+                        .unwrap())
+                }
+            }
+        }
+    };
+    auth_middleware(req, pool, run).await
+}
@@ -1,2 +1,3 @@
+pub mod book;
 pub mod params;
 pub mod session;
@@ -1,4 +1,5 @@
 pub mod api;
 pub mod config;
+pub mod events;
 pub mod middlewares;
 pub mod web;
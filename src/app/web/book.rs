@@ -1,12 +1,20 @@
-use super::common::{html_str_handler, HandlerResult};
+use super::common::{html_str_handler, page_not_found, HandlerResult};
 use crate::{
-    app::web::{
-        middleware_auth,
-        templates::{
-            AddBookOwnerTemplate, AddNewBookTemplate, BookListsBookTemplate, EditBookTemplate,
+    app::{
+        api::{get_book_default_id, get_session_account_id},
+        middlewares::book::active_book,
+        web::templates::{
+            AddNewBookTemplate, BookListsBookTemplate, BookSwitcherTemplate, EditBookTemplate,
+            InvitePeopleTemplate,
+        },
+    },
+    database::{
+        model::{book::ROLES, preset::Preset},
+        querier::{
+            book::{get_by_account_id, get_by_id, get_count, get_members, get_role},
+            invitation::get_pending_by_book,
         },
     },
-    database::querier::book::{get_by_account_id, get_by_id, get_count},
     utils::serve_empty,
 };
 use askama::Template;
@@ -18,51 +26,93 @@ pub async fn page_book_create(_: Request<Incoming>, pool: PgPool, id: Ulid) -> H
     if get_count(&pool, id).await == 0 {
         let template = AddNewBookTemplate {
             is_first_time: true,
+            presets: Preset::all(),
         };
         let html = template.render().expect("Should render markup");
         html_str_handler(&html).await
     } else {
         let template = AddNewBookTemplate {
             is_first_time: false,
+            presets: Preset::all(),
         };
         let html = template.render().expect("Should render markup");
         html_str_handler(&html).await
     }
 }
 
-pub async fn page_book_add_owner(req: Request<Incoming>, pool: PgPool) -> HandlerResult {
-    if middleware_auth(&req, &pool).await.is_some() {
-        let template = AddBookOwnerTemplate::default();
-        let html = template.render().expect("Should render markup");
-        html_str_handler(&html).await
-    } else {
-        Ok(Response::builder()
-            .status(StatusCode::TEMPORARY_REDIRECT)
-            .header(LOCATION, "/login")
-            .body(serve_empty())
-            .unwrap())
+// invites people to the book of the `book` cookie
+pub async fn page_book_invite(req: Request<Incoming>, pool: PgPool, id: Ulid) -> HandlerResult {
+    let book_id = get_book_default_id(req.headers()).await.unwrap();
+    if !get_role(&pool, book_id, id)
+        .await
+        .is_some_and(|r| r.can_manage())
+    {
+        return page_not_found().await;
     }
+    let template = InvitePeopleTemplate {
+        book_id: book_id.to_string(),
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
 }
 
-pub async fn page_books(_: Request<Incoming>, pool: PgPool, id: Ulid) -> HandlerResult {
+pub async fn page_books(req: Request<Incoming>, pool: PgPool, id: Ulid) -> HandlerResult {
+    let current = active_book(&pool, id, req.headers()).await;
     let datas = get_by_account_id(id, pool).await;
-    let template = BookListsBookTemplate { books: &datas };
+    let template = BookListsBookTemplate {
+        books: &datas,
+        current_id: current.map_or(String::new(), |c| c.to_string()),
+    };
     let html = template.render().expect("Should render markup");
     html_str_handler(&html).await
 }
 
-pub async fn page_book_edit(_: Request<Incoming>, pool: PgPool, id: Ulid) -> HandlerResult {
-    let mut can_delete = true;
-    if get_count(&pool, id).await > 1 {
-        can_delete = false
-    }
+// the book picker in the dashboard header, empty for accounts without books
+pub async fn fragment_book_switcher(
+    req: Request<Incoming>,
+    pool: PgPool,
+    id: Ulid,
+) -> HandlerResult {
+    let current = active_book(&pool, id, req.headers()).await;
+    let datas = get_by_account_id(id, pool).await;
+    let template = BookSwitcherTemplate {
+        books: &datas,
+        current_id: current.map_or(String::new(), |c| c.to_string()),
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+pub async fn page_book_edit(req: Request<Incoming>, pool: PgPool, id: Ulid) -> HandlerResult {
+    // the route only knows the book from the path, so look up who is asking
+    let account_id = get_session_account_id(&req, &pool)
+        .await
+        .unwrap_or_default();
+    let role = match get_role(&pool, id, account_id).await {
+        Some(r) => r,
+        None => return page_not_found().await,
+    };
+    let members = get_members(id, &pool).await;
+    let invitations = if role.can_manage() {
+        get_pending_by_book(id, &pool).await
+    } else {
+        Vec::new()
+    };
     match get_by_id(pool, id).await {
         Some(book) => {
+            let settings = book.settings();
             let template = EditBookTemplate {
                 id: book.id.to_string(),
                 name: book.name,
                 description: book.description,
-                is_can_delete: can_delete,
+                timezone: settings.timezone.name().to_string(),
+                month_start_day: settings.month_start_day,
+                is_can_delete: role.can_manage(),
+                is_owner: role.can_manage(),
+                account_id,
+                members: &members,
+                roles: &ROLES,
+                invitations: &invitations,
             };
             let html = template.render().expect("Should render markup");
             html_str_handler(&html).await
@@ -1,26 +1,53 @@
 use super::{
-    common::{html_str_handler, HandlerResult},
-    templates::{AddNewCategoryTemplate, CategoryListsTemplate, EditCategoryTemplate},
+    common::{html_str_handler, page_not_found, HandlerResult},
+    templates::{
+        AddNewCategoryTemplate, CategoryListsTemplate, CategoryTreeView, EditCategoryTemplate,
+    },
 };
 use crate::{
     app::api::get_book_default_id,
-    database::querier::category::{get_by_book_id, get_by_id},
-    utils::serve_empty,
+    database::{
+        model::category::{tree, Category, DEFAULT_COLOR, ICONS},
+        querier::category::{get_by_book_id, get_by_id},
+    },
+    utils::parse_query,
 };
 use askama::Template;
-use hyper::{body::Incoming, header::LOCATION, Request, Response, StatusCode};
+use hyper::{body::Incoming, Request};
 use sqlx::PgPool;
 use ulid::Ulid;
 
-pub async fn page_category_create(req: Request<Incoming>, _: PgPool, _: Ulid) -> HandlerResult {
+pub fn tree_views(categories: &[Category]) -> Vec<CategoryTreeView> {
+    tree(categories)
+        .into_iter()
+        .map(|(c, depth)| CategoryTreeView {
+            id: c.id,
+            label: format!("{}{}", "\u{a0}".repeat(depth * 4), c.name),
+            name: c.name,
+            depth,
+            is_expense: c.is_expense,
+            color: c.color,
+            icon: c.icon,
+            record_count: c.record_count,
+            is_archived: c.archived_at.is_some(),
+        })
+        .collect()
+}
+
+pub async fn page_category_create(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
     let book_id: Ulid;
     {
         let header = req.headers();
         let id = get_book_default_id(header);
         book_id = id.await.unwrap();
     }
+    let mut cats = get_by_book_id(book_id, pool).await;
+    cats.retain(|c| c.archived_at.is_none());
     let template = AddNewCategoryTemplate {
         id: book_id.to_string(),
+        parents: &tree_views(&cats),
+        color: DEFAULT_COLOR.to_string(),
+        icons: &ICONS,
     };
     let html = template.render().expect("Should render markup");
     html_str_handler(&html).await
@@ -33,26 +60,73 @@ pub async fn page_categories(req: Request<Incoming>, pool: PgPool, _: Ulid) -> H
         let id = get_book_default_id(header);
         book_id = id.await.unwrap();
     }
-    let datas = get_by_book_id(book_id, pool).await;
-    let template = CategoryListsTemplate { categories: &datas };
+    // archived categories are only listed when asked for with `archived=1`
+    let show_archived = parse_query(req.uri().query())
+        .get("archived")
+        .is_some_and(|v| v == "1");
+    let mut datas = get_by_book_id(book_id, pool).await;
+    let archived_count = datas.iter().filter(|c| c.archived_at.is_some()).count();
+    if !show_archived {
+        datas.retain(|c| c.archived_at.is_none());
+    }
+    let template = CategoryListsTemplate {
+        categories: &tree_views(&datas),
+        show_archived,
+        archived_count,
+    };
     let html = template.render().expect("Should render markup");
     html_str_handler(&html).await
 }
 
-pub async fn page_category_edit(_: Request<Incoming>, pool: PgPool, id: Ulid) -> HandlerResult {
-    if let Some(category) = get_by_id(id, pool).await {
+pub async fn page_category_edit(req: Request<Incoming>, pool: PgPool, id: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    if let Some(category) = get_by_id(id, pool.clone())
+        .await
+        .filter(|c| c.book_id == book_id)
+    {
+        // any category outside its own subtree can become the parent, the
+        // page hides the ones of the other type; only those of the same type
+        // can receive its records in a merge
+        let cats = get_by_book_id(category.book_id, pool).await;
+        let parents: Vec<CategoryTreeView> = tree_views(&cats)
+            .into_iter()
+            .filter(|c| !category.would_cycle(c.id, &cats))
+            .collect();
+        let merge_targets: Vec<CategoryTreeView> = parents
+            .iter()
+            .filter(|c| c.is_expense == category.is_expense)
+            .cloned()
+            .collect();
+        let replacements: Vec<CategoryTreeView> = tree_views(&cats)
+            .into_iter()
+            .filter(|c| c.is_expense == category.is_expense && c.id != category.id)
+            .collect();
         let template = EditCategoryTemplate {
             id: category.id.to_string(),
             name: category.name,
             description: category.description,
+            parent_id: category
+                .parent_id
+                .map_or("".to_string(), |id| id.to_string()),
+            parents: &parents,
+            is_expense: category.is_expense,
+            color: category.color,
+            icon: category.icon,
+            icons: &ICONS,
+            record_count: category.record_count,
+            is_archived: category.archived_at.is_some(),
+            merge_targets: &merge_targets,
+            replacements: &replacements,
+            version: category.version,
         };
         let html = template.render().expect("Should render markup");
         html_str_handler(&html).await
     } else {
-        Ok(Response::builder()
-            .status(StatusCode::TEMPORARY_REDIRECT)
-            .header(LOCATION, "/login")
-            .body(serve_empty())
-            .unwrap())
+        page_not_found().await
     }
 }
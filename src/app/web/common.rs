@@ -1,7 +1,17 @@
 use super::templates::{IndexTemplate, LoginTemplate, NotFoundTemplate, RegisterTemplate};
 use crate::{
-    app::web::{middleware_auth, templates::DashboardTemplate},
-    database::querier::book::get_count,
+    app::{
+        middlewares::book::active_book,
+        web::{goal::goal_view, middleware_auth, templates::DashboardTemplate},
+    },
+    database::{
+        model::preset::Preset,
+        querier::{
+            book::{get_count, get_settings},
+            goal,
+            invitation::get_pending_by_account,
+        },
+    },
     utils::serve_empty,
 };
 use askama::Template;
@@ -79,7 +89,9 @@ pub async fn page_index() -> HandlerResult {
 }
 
 pub async fn page_signup() -> HandlerResult {
-    let template = RegisterTemplate::default();
+    let template = RegisterTemplate {
+        presets: Preset::all(),
+    };
     let html = template.render().expect("Should render markup");
     html_str_handler(&html).await
 }
@@ -98,14 +110,31 @@ pub async fn page_not_found() -> HandlerResult {
 
 pub async fn page_dashboard(req: Request<Incoming>, pool: PgPool) -> HandlerResult {
     if let Some(id) = middleware_auth(&req, &pool).await {
-        if get_count(&pool, id).await == 0 {
+        // people without a book of their own may still have been invited to one
+        let invitations = get_pending_by_account(id, &pool).await;
+        if get_count(&pool, id).await == 0 && invitations.is_empty() {
             Ok(Response::builder()
                 .status(StatusCode::SEE_OTHER)
                 .header(LOCATION, "/book/create")
                 .body(serve_empty())
                 .unwrap())
         } else {
-            let template = DashboardTemplate::default();
+            let goals = match active_book(&pool, id, req.headers()).await {
+                Some(book_id) => {
+                    let today = get_settings(&pool, book_id).await.today();
+                    goal::get_by_book_id(book_id, &pool)
+                        .await
+                        .iter()
+                        .filter(|g| !g.is_reached())
+                        .map(|g| goal_view(g, today))
+                        .collect()
+                }
+                None => Vec::new(),
+            };
+            let template = DashboardTemplate {
+                goals: &goals,
+                invitations: &invitations,
+            };
             let html = template.render().expect("Should render markup");
             html_str_handler(&html).await
         }
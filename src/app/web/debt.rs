@@ -0,0 +1,141 @@
+use super::{
+    common::{html_str_handler, HandlerResult},
+    templates::{
+        AddDebtTemplate, DebtDetailTemplate, DebtListsTemplate, DebtPaymentWithRupiah, DebtView,
+        InstallmentView,
+    },
+};
+use crate::{
+    app::api::get_book_default_id,
+    database::{
+        model::debt::{Debt, Installment},
+        querier::{
+            book::get_settings,
+            debt::{
+                get_by_book_id, get_by_id, get_installments, get_installments_by_book_id,
+                get_payments,
+            },
+            record::fetch,
+        },
+    },
+    utils::{format_rupiah, serve_empty},
+};
+use askama::Template;
+use chrono::NaiveDate;
+use hyper::{body::Incoming, header::LOCATION, Request, Response, StatusCode};
+use sqlx::PgPool;
+use ulid::Ulid;
+
+fn debt_view(debt: &Debt, installments: &[Installment], today: NaiveDate) -> DebtView {
+    DebtView {
+        id: debt.id.to_string(),
+        counterparty: debt.counterparty.clone(),
+        notes: debt.notes.clone(),
+        principal: format_rupiah(debt.principal),
+        paid: format_rupiah(debt.paid),
+        outstanding: format_rupiah(debt.outstanding()),
+        is_receivable: debt.is_receivable,
+        is_settled: debt.is_settled(),
+        overdue_count: debt.overdue_installments(installments, today).len(),
+    }
+}
+
+pub async fn page_debts(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let settings = get_settings(&pool, book_id).await;
+    let today = settings.today();
+    let debts = get_by_book_id(book_id, &pool).await;
+    let installments = get_installments_by_book_id(book_id, &pool).await;
+    let mut receivable = 0.0;
+    let mut payable = 0.0;
+    let mut datas: Vec<DebtView> = Vec::new();
+    for debt in &debts {
+        if debt.is_receivable {
+            receivable += debt.outstanding();
+        } else {
+            payable += debt.outstanding();
+        }
+        let own: Vec<Installment> = installments
+            .iter()
+            .filter(|i| i.debt_id == debt.id)
+            .cloned()
+            .collect();
+        datas.push(debt_view(debt, &own, today));
+    }
+    let template = DebtListsTemplate {
+        receivable: format_rupiah(receivable),
+        payable: format_rupiah(payable),
+        debts: &datas,
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+pub async fn page_debt_create(req: Request<Incoming>, _: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let template = AddDebtTemplate {
+        id: book_id.to_string(),
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+pub async fn page_debt_detail(req: Request<Incoming>, pool: PgPool, id: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    match get_by_id(id, &pool).await {
+        Some(debt) if debt.book_id == book_id => {
+            let settings = get_settings(&pool, book_id).await;
+            let today = settings.today();
+            let installments = get_installments(debt.id, &pool).await;
+            let paid_count = debt.paid_installments(&installments);
+            let schedule: Vec<InstallmentView> = installments
+                .iter()
+                .enumerate()
+                .map(|(i, installment)| InstallmentView {
+                    due_date: installment.due_date.format("%e %b %Y").to_string(),
+                    amount_in_rupiah: format_rupiah(installment.amount),
+                    is_paid: i < paid_count,
+                    is_overdue: i >= paid_count && installment.due_date < today,
+                })
+                .collect();
+            let payments: Vec<DebtPaymentWithRupiah> = get_payments(debt.id, &pool)
+                .await
+                .iter()
+                .map(|p| DebtPaymentWithRupiah {
+                    payment: p.clone(),
+                    amount_in_rupiah: format_rupiah(p.amount),
+                    formatted_date: settings.format(p.created_at, "%e %b %Y"),
+                })
+                .collect();
+            let records = fetch(book_id, "", 20, pool).await;
+            let template = DebtDetailTemplate {
+                debt: debt_view(&debt, &installments, today),
+                installments: &schedule,
+                payments: &payments,
+                records: &records,
+            };
+            let html = template.render().expect("Should render markup");
+            html_str_handler(&html).await
+        }
+        _ => Ok(Response::builder()
+            .status(StatusCode::SEE_OTHER)
+            .header(LOCATION, "/debt")
+            .body(serve_empty())
+            .unwrap()),
+    }
+}
@@ -0,0 +1,112 @@
+use super::{
+    common::{html_str_handler, HandlerResult},
+    templates::{
+        AddGoalTemplate, ContributionWithRupiah, GoalDetailTemplate, GoalListsTemplate, GoalView,
+    },
+};
+use crate::{
+    app::api::get_book_default_id,
+    database::{
+        model::goal::Goal,
+        querier::{
+            book::get_settings,
+            goal::{get_by_book_id, get_by_id, get_contributions},
+            record::fetch,
+        },
+    },
+    utils::{format_rupiah, serve_empty},
+};
+use askama::Template;
+use chrono::NaiveDate;
+use hyper::{body::Incoming, header::LOCATION, Request, Response, StatusCode};
+use sqlx::PgPool;
+use ulid::Ulid;
+
+pub fn goal_view(goal: &Goal, today: NaiveDate) -> GoalView {
+    let projected = goal.projected_completion(today);
+    GoalView {
+        id: goal.id.to_string(),
+        name: goal.name.clone(),
+        saved: format_rupiah(goal.saved),
+        target_amount: format_rupiah(goal.target_amount),
+        target_date: goal.target_date.format("%e %b %Y").to_string(),
+        progress: format!("{:.0}", goal.progress()),
+        required_monthly: format_rupiah(goal.required_monthly(today)),
+        projected_completion: match projected {
+            Some(d) => d.format("%e %b %Y").to_string(),
+            None => "-".to_string(),
+        },
+        is_reached: goal.is_reached(),
+        is_behind: projected.is_none_or(|d| d > goal.target_date),
+    }
+}
+
+pub async fn page_goals(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let settings = get_settings(&pool, book_id).await;
+    let today = settings.today();
+    let datas: Vec<GoalView> = get_by_book_id(book_id, &pool)
+        .await
+        .iter()
+        .map(|g| goal_view(g, today))
+        .collect();
+    let template = GoalListsTemplate { goals: &datas };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+pub async fn page_goal_create(req: Request<Incoming>, _: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let template = AddGoalTemplate {
+        id: book_id.to_string(),
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+pub async fn page_goal_detail(req: Request<Incoming>, pool: PgPool, id: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    match get_by_id(id, &pool).await {
+        Some(goal) if goal.book_id == book_id => {
+            let settings = get_settings(&pool, book_id).await;
+            let today = settings.today();
+            let contributions: Vec<ContributionWithRupiah> = get_contributions(goal.id, &pool)
+                .await
+                .iter()
+                .map(|c| ContributionWithRupiah {
+                    contribution: c.clone(),
+                    amount_in_rupiah: format_rupiah(c.amount),
+                    formatted_date: settings.format(c.created_at, "%e %b %Y"),
+                })
+                .collect();
+            let records = fetch(book_id, "", 20, pool).await;
+            let template = GoalDetailTemplate {
+                goal: goal_view(&goal, today),
+                contributions: &contributions,
+                records: &records,
+            };
+            let html = template.render().expect("Should render markup");
+            html_str_handler(&html).await
+        }
+        _ => Ok(Response::builder()
+            .status(StatusCode::SEE_OTHER)
+            .header(LOCATION, "/goal")
+            .body(serve_empty())
+            .unwrap()),
+    }
+}
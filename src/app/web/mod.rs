@@ -6,7 +6,14 @@ use ulid::Ulid;
 pub mod book;
 pub mod category;
 pub mod common;
+pub mod debt;
+pub mod goal;
+pub mod payee;
 pub mod record;
+pub mod recurring;
+pub mod report;
+pub mod rule;
+pub mod split;
 pub mod templates;
 
 pub async fn middleware_auth(req: &Request<Incoming>, pool: &PgPool) -> Option<Ulid> {
@@ -0,0 +1,73 @@
+use super::{
+    category::tree_views,
+    common::{html_str_handler, HandlerResult},
+    templates::{PayeeListsTemplate, PayeeOptionsTemplate, PayeeView},
+};
+use crate::{
+    app::api::{
+        get_book_default_id,
+        report::{range_from_params, range_query},
+    },
+    database::querier::{book::get_settings, category::get_by_book_id, payee},
+    utils::{format_rupiah, parse_query},
+};
+use askama::Template;
+use hyper::{body::Incoming, Request};
+use sqlx::PgPool;
+use ulid::Ulid;
+
+pub async fn page_payees(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let params = parse_query(req.uri().query());
+    let range = range_from_params(&params, get_settings(&pool, book_id).await);
+    let datas: Vec<PayeeView> = payee::totals(&pool, book_id, range.start(), range.end())
+        .await
+        .into_iter()
+        .map(|t| PayeeView {
+            id: t.payee.id.to_string(),
+            name: t.payee.name,
+            default_category_id: t
+                .payee
+                .default_category_id
+                .map_or("".to_string(), |id| id.to_string()),
+            amount_in_rupiah: format_rupiah(t.total),
+            record_count: t.record_count,
+        })
+        .collect();
+    let cats = get_by_book_id(book_id, pool).await;
+    let template = PayeeListsTemplate {
+        from: range.from.to_string(),
+        to: range.last_day().to_string(),
+        previous_query: range_query(&range.previous()),
+        next_query: range_query(&range.next()),
+        payees: &datas,
+        categories: &tree_views(&cats),
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+// `<option>` list of payees matching the `payee` query for the record form
+pub async fn fragment_payee_options(
+    req: Request<Incoming>,
+    pool: PgPool,
+    _: Ulid,
+) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let params = parse_query(req.uri().query());
+    let query = params.get("payee").map_or("", |v| v.as_str());
+    let datas = payee::search(book_id, query, &pool).await;
+    let template = PayeeOptionsTemplate { payees: &datas };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
@@ -1,18 +1,31 @@
 use super::{
-    common::{html_str_handler, HandlerResult},
-    templates::{AddRecordTemplate, EditRecordTemplate, RecordWithRupiah},
+    category::tree_views,
+    common::{html_str_handler, page_not_found, HandlerResult},
+    templates::{
+        AddRecordTemplate, CategorySuggestionsTemplate, CategoryTreeView, EditRecordTemplate,
+        RecordWithRupiah,
+    },
 };
+use crate::app::web::templates::RecordListsTemplate;
 use crate::{
     app::api::get_book_default_id,
-    database::querier::{
-        category::get_by_book_id,
-        record::{fetch, get_by_id},
+    database::{
+        model::{
+            category::{Category, CategorySuggestion},
+            record::note_keywords,
+            rule::{first_match, Matcher, Rule, RuleKind},
+        },
+        querier::{
+            book::get_settings,
+            category::{get_by_usage, suggest},
+            record::{fetch, get_by_id},
+            rule,
+        },
     },
-    utils::format_rupiah,
+    utils::{format_rupiah, parse_query},
 };
-use crate::{app::web::templates::RecordListsTemplate, utils::serve_empty};
 use askama::Template;
-use hyper::{body::Incoming, header::LOCATION, Request, Response, StatusCode};
+use hyper::{body::Incoming, Request};
 use sqlx::PgPool;
 use ulid::Ulid;
 
@@ -23,13 +36,14 @@ pub async fn page_records(req: Request<Incoming>, pool: PgPool, _: Ulid) -> Hand
         let id = get_book_default_id(header);
         book_id = id.await.unwrap();
     }
+    let settings = get_settings(&pool, book_id).await;
     let datas = fetch(book_id, "", 10, pool).await;
     let mut datas_with_rupiah: Vec<RecordWithRupiah> = Vec::new();
     for data in &datas {
         let data_with_rupiah: RecordWithRupiah = RecordWithRupiah {
             record: data.clone(),
             amount_in_rupiah: format_rupiah(data.amount),
-            formatted_date: data.created_at.format("%e %b %Y").to_string(),
+            formatted_date: settings.format(data.created_at, "%e %b %Y"),
         };
         datas_with_rupiah.push(data_with_rupiah);
     }
@@ -47,10 +61,25 @@ pub async fn page_record_create(req: Request<Incoming>, pool: PgPool, _: Ulid) -
         let id = get_book_default_id(header);
         book_id = id.await.unwrap();
     }
-    let cats = get_by_book_id(book_id, pool).await;
+    let mut cats = get_by_usage(book_id, &pool).await;
+    cats.retain(|c| c.archived_at.is_none());
+    let categories = tree_views(&cats);
+    // the few categories used the most lately, listed above the full tree
+    let mut used: Vec<&Category> = cats.iter().filter(|c| c.usage_score > 0.0).collect();
+    used.sort_by(|a, b| b.usage_score.total_cmp(&a.usage_score));
+    let frequent: Vec<CategoryTreeView> = used
+        .iter()
+        .take(5)
+        .filter_map(|u| categories.iter().find(|c| c.id == u.id))
+        .map(|c| CategoryTreeView {
+            label: c.name.clone(),
+            ..c.clone()
+        })
+        .collect();
     let template = AddRecordTemplate {
         id: book_id.to_string(),
-        categories: &cats,
+        frequent: &frequent,
+        categories: &categories,
     };
     let html = template.render().expect("Should render markup");
     html_str_handler(&html).await
@@ -63,23 +92,72 @@ pub async fn page_record_edit(req: Request<Incoming>, pool: PgPool, id: Ulid) ->
         let id = get_book_default_id(header);
         book_id = id.await.unwrap();
     }
-    let pool2 = pool.clone();
-    if let Some(record) = get_by_id(id, pool2).await {
-        let cats = get_by_book_id(book_id, pool).await;
+    if let Some(record) = get_by_id(id, pool.clone())
+        .await
+        .filter(|r| r.book_id == book_id)
+    {
+        // an archived category stays selectable for the records already in it
+        let mut cats = get_by_usage(book_id, &pool).await;
+        cats.retain(|c| c.archived_at.is_none() || c.id == record.category_id);
         let template = EditRecordTemplate {
             id: record.id.to_string(),
             notes: record.notes,
+            payee: record.payee_name,
             amount: record.amount,
             category_id: record.category_id,
-            categories: &cats,
+            categories: &tree_views(&cats),
+            version: record.version,
         };
         let html = template.render().expect("Should render markup");
         html_str_handler(&html).await
     } else {
-        Ok(Response::builder()
-            .status(StatusCode::TEMPORARY_REDIRECT)
-            .header(LOCATION, "/login")
-            .body(serve_empty())
-            .unwrap())
+        page_not_found().await
+    }
+}
+
+// categories likely to fit the `notes` typed so far, from the rules and from
+// earlier records of the book with similar notes
+pub async fn fragment_category_suggestions(
+    req: Request<Incoming>,
+    pool: PgPool,
+    _: Ulid,
+) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let params = parse_query(req.uri().query());
+    let notes = params.get("notes").map_or("", |v| v.as_str());
+    let keywords = note_keywords(notes);
+    let mut datas: Vec<CategorySuggestion> = Vec::new();
+    if !keywords.is_empty() {
+        // the amount is not known yet, so only rules on the notes alone count
+        let rules: Vec<Rule> = rule::get_by_book_id(book_id, &pool)
+            .await
+            .into_iter()
+            .filter(|r| {
+                r.kind != RuleKind::Amount && r.min_amount.is_none() && r.max_amount.is_none()
+            })
+            .collect();
+        let matchers: Vec<Matcher> = rules.iter().map(Rule::matcher).collect();
+        if let Some(r) = first_match(&matchers, notes, 0.0) {
+            datas.push(CategorySuggestion {
+                category_id: r.category_id,
+                name: r.category_name.clone(),
+                record_count: 0,
+            });
+        }
+        for s in suggest(book_id, &keywords, &pool).await {
+            if !datas.iter().any(|d| d.category_id == s.category_id) {
+                datas.push(s);
+            }
+        }
     }
+    let template = CategorySuggestionsTemplate {
+        suggestions: &datas,
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
 }
@@ -0,0 +1,53 @@
+use super::{
+    category::tree_views,
+    common::{html_str_handler, HandlerResult},
+    templates::{AddRecurringTemplate, RecurringListsTemplate, RecurringWithRupiah},
+};
+use crate::{
+    app::api::get_book_default_id,
+    database::querier::{category::get_by_book_id, recurring},
+    utils::format_rupiah,
+};
+use askama::Template;
+use hyper::{body::Incoming, Request};
+use sqlx::PgPool;
+use ulid::Ulid;
+
+pub async fn page_recurrings(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let datas = recurring::get_by_book_id(book_id, &pool).await;
+    let mut datas_with_rupiah: Vec<RecurringWithRupiah> = Vec::new();
+    for data in &datas {
+        datas_with_rupiah.push(RecurringWithRupiah {
+            recurring: data.clone(),
+            amount_in_rupiah: format_rupiah(data.amount),
+        });
+    }
+    let template = RecurringListsTemplate {
+        recurrings: &datas_with_rupiah,
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+pub async fn page_recurring_create(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let mut cats = get_by_book_id(book_id, pool).await;
+    cats.retain(|c| c.archived_at.is_none());
+    let template = AddRecurringTemplate {
+        id: book_id.to_string(),
+        categories: &tree_views(&cats),
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
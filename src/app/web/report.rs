@@ -0,0 +1,261 @@
+use super::{
+    common::{html_str_handler, HandlerResult},
+    templates::{
+        BookTotalView, CategoryShareView, CategoryTrendView, ForecastLineView, ForecastMonthView,
+        ForecastTemplate, OverviewTemplate, RecordWithRupiah, ReportCategoryTemplate,
+        ReportTemplate, TrendTemplate,
+    },
+};
+use crate::{
+    app::api::{
+        get_book_default_id,
+        report::{
+            forecast_months_from_params, load_forecast, load_overview, load_trend,
+            range_from_params, range_query, trend_from_params,
+        },
+    },
+    database::{
+        model::{
+            book::BookSettings,
+            report::{CategoryTotal, Report},
+        },
+        querier::{
+            book::get_settings, category::get_by_id, record::fetch_by_category,
+            report::rolled_up_category_totals,
+        },
+    },
+    utils::{format_rupiah, parse_query, serve_empty},
+};
+use askama::Template;
+use hyper::{body::Incoming, header::LOCATION, Request, Response, StatusCode};
+use sqlx::PgPool;
+use ulid::Ulid;
+
+fn share_views(report: &Report, totals: &[CategoryTotal]) -> Vec<CategoryShareView> {
+    totals
+        .iter()
+        .map(|t| CategoryShareView {
+            category_id: t.category_id.to_string(),
+            name: t.name.clone(),
+            color: t.color.clone(),
+            icon: t.icon.clone(),
+            amount_in_rupiah: format_rupiah(t.total),
+            percentage: format!("{:.1}", report.percentage(t)),
+            record_count: t.record_count,
+        })
+        .collect()
+}
+
+pub async fn page_report(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let params = parse_query(req.uri().query());
+    let settings = get_settings(&pool, book_id).await;
+    let range = range_from_params(&params, settings);
+    let totals = rolled_up_category_totals(&pool, book_id, range.start(), range.end()).await;
+    let report = Report::new(range, totals);
+    let incomes = share_views(&report, &report.incomes);
+    let expenses = share_views(&report, &report.expenses);
+    let template = ReportTemplate {
+        period: range.period.as_str().to_string(),
+        from: range.from.to_string(),
+        to: range.last_day().to_string(),
+        query: range_query(&range),
+        previous_query: range_query(&range.previous()),
+        next_query: range_query(&range.next()),
+        total_income: format_rupiah(report.total_income),
+        total_expense: format_rupiah(report.total_expense),
+        net: format_rupiah(report.net().abs()),
+        is_net_negative: report.net() < 0.0,
+        incomes: &incomes,
+        expenses: &expenses,
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+pub async fn page_overview(
+    req: Request<Incoming>,
+    pool: PgPool,
+    account_id: Ulid,
+) -> HandlerResult {
+    let params = parse_query(req.uri().query());
+    let overview = load_overview(&pool, account_id, &params).await;
+    let range = overview.range;
+    let books: Vec<BookTotalView> = overview
+        .books
+        .iter()
+        .map(|b| BookTotalView {
+            name: b.name.clone(),
+            total_income: format_rupiah(b.total_income),
+            total_expense: format_rupiah(b.total_expense),
+            net: format_rupiah(b.net().abs()),
+            is_net_negative: b.net() < 0.0,
+        })
+        .collect();
+    let mut records: Vec<RecordWithRupiah> = Vec::new();
+    for data in &overview.records {
+        let settings = overview
+            .books
+            .iter()
+            .find(|b| b.book_id == data.book_id)
+            .map_or(BookSettings::default(), |b| b.settings);
+        records.push(RecordWithRupiah {
+            record: data.clone(),
+            amount_in_rupiah: format_rupiah(data.amount),
+            formatted_date: settings.format(data.created_at, "%e %b %Y"),
+        });
+    }
+    let template = OverviewTemplate {
+        period: range.period.as_str().to_string(),
+        from: range.from.to_string(),
+        to: range.last_day().to_string(),
+        previous_query: range_query(&range.previous()),
+        next_query: range_query(&range.next()),
+        total_income: format_rupiah(overview.total_income()),
+        total_expense: format_rupiah(overview.total_expense()),
+        net: format_rupiah(overview.net().abs()),
+        is_net_negative: overview.net() < 0.0,
+        books: &books,
+        records: &records,
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+pub async fn page_report_category(req: Request<Incoming>, pool: PgPool, id: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let params = parse_query(req.uri().query());
+    let settings = get_settings(&pool, book_id).await;
+    let range = range_from_params(&params, settings);
+    match get_by_id(id, pool.clone()).await {
+        Some(category) if category.book_id == book_id => {
+            let datas = fetch_by_category(book_id, id, range.start(), range.end(), &pool).await;
+            let total: f32 = datas.iter().map(|r| r.amount).sum();
+            let mut datas_with_rupiah: Vec<RecordWithRupiah> = Vec::new();
+            for data in &datas {
+                datas_with_rupiah.push(RecordWithRupiah {
+                    record: data.clone(),
+                    amount_in_rupiah: format_rupiah(data.amount),
+                    formatted_date: settings.format(data.created_at, "%e %b %Y"),
+                });
+            }
+            let template = ReportCategoryTemplate {
+                name: category.name,
+                is_expense: category.is_expense,
+                from: range.from.to_string(),
+                to: range.last_day().to_string(),
+                query: range_query(&range),
+                total: format_rupiah(total),
+                records: &datas_with_rupiah,
+            };
+            let html = template.render().expect("Should render markup");
+            html_str_handler(&html).await
+        }
+        _ => Ok(Response::builder()
+            .status(StatusCode::SEE_OTHER)
+            .header(LOCATION, "/report")
+            .body(serve_empty())
+            .unwrap()),
+    }
+}
+
+pub async fn page_trend(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let params = parse_query(req.uri().query());
+    let settings = get_settings(&pool, book_id).await;
+    let (bucket, buckets) = trend_from_params(&params, &settings);
+    let trend = load_trend(&pool, book_id, &settings, bucket, buckets).await;
+    let increases: Vec<Ulid> = trend
+        .largest_increases(3)
+        .iter()
+        .map(|c| c.category_id)
+        .collect();
+    let categories: Vec<CategoryTrendView> = trend
+        .categories
+        .iter()
+        .map(|c| CategoryTrendView {
+            name: c.name.clone(),
+            is_expense: c.is_expense,
+            totals: c.totals.iter().map(|t| format_rupiah(*t)).collect(),
+            change: format_rupiah(c.change().abs()),
+            is_increase: c.change() > 0.0,
+            change_percentage: match c.change_percentage() {
+                Some(p) => format!("{:+.1}%", p),
+                None => "-".to_string(),
+            },
+            is_highlighted: increases.contains(&c.category_id),
+        })
+        .collect();
+    let highlights: Vec<&CategoryTrendView> =
+        categories.iter().filter(|c| c.is_highlighted).collect();
+    let template = TrendTemplate {
+        bucket: bucket.as_str().to_string(),
+        count: trend.buckets.len(),
+        labels: trend.buckets.iter().map(|b| bucket.label(*b)).collect(),
+        categories: &categories,
+        highlights: &highlights,
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+pub async fn page_forecast(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let params = parse_query(req.uri().query());
+    let months = forecast_months_from_params(&params);
+    let settings = get_settings(&pool, book_id).await;
+    let forecast = load_forecast(&pool, book_id, &settings, months).await;
+    let datas: Vec<ForecastMonthView> = forecast
+        .months
+        .iter()
+        .map(|m| ForecastMonthView {
+            label: m.month.format("%B %Y").to_string(),
+            income: format_rupiah(m.income),
+            expense: format_rupiah(m.expense),
+            closing_balance: format_rupiah(m.closing_balance.abs()),
+            is_negative: m.is_negative(),
+            lines: m
+                .lines
+                .iter()
+                .map(|l| ForecastLineView {
+                    date: match l.date {
+                        Some(d) => d.format("%e %b").to_string(),
+                        None => "".to_string(),
+                    },
+                    name: l.name.clone(),
+                    amount_in_rupiah: format_rupiah(l.amount),
+                    is_expense: l.is_expense,
+                    is_estimate: l.is_estimate,
+                })
+                .collect(),
+        })
+        .collect();
+    let template = ForecastTemplate {
+        months,
+        opening_balance: format_rupiah(forecast.opening_balance.abs()),
+        is_opening_negative: forecast.opening_balance < 0.0,
+        forecasts: &datas,
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
@@ -0,0 +1,73 @@
+use super::{
+    category::tree_views,
+    common::{html_str_handler, HandlerResult},
+    templates::{AddRuleTemplate, RuleListsTemplate, RuleView},
+};
+use crate::{
+    app::api::get_book_default_id,
+    database::{
+        model::rule::RuleKind,
+        querier::{category::get_by_book_id, rule},
+    },
+    utils::format_rupiah,
+};
+use askama::Template;
+use hyper::{body::Incoming, Request};
+use sqlx::PgPool;
+use ulid::Ulid;
+
+pub async fn page_rules(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let datas: Vec<RuleView> = rule::get_by_book_id(book_id, &pool)
+        .await
+        .into_iter()
+        .map(|r| {
+            let condition = match r.kind {
+                RuleKind::Contains => format!("Notes contain \"{}\"", r.pattern),
+                RuleKind::Regex => format!("Notes match /{}/", r.pattern),
+                RuleKind::Amount => "Any notes".to_string(),
+            };
+            let range = match (r.min_amount, r.max_amount) {
+                (Some(min), Some(max)) => {
+                    format!(
+                        " and amount {} - {}",
+                        format_rupiah(min),
+                        format_rupiah(max)
+                    )
+                }
+                (Some(min), None) => format!(" and amount from {}", format_rupiah(min)),
+                (None, Some(max)) => format!(" and amount up to {}", format_rupiah(max)),
+                (None, None) => "".to_string(),
+            };
+            RuleView {
+                id: r.id.to_string(),
+                condition: format!("{}{}", condition, range),
+                category_name: r.category_name,
+            }
+        })
+        .collect();
+    let template = RuleListsTemplate { rules: &datas };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+pub async fn page_rule_create(req: Request<Incoming>, pool: PgPool, _: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let cats = get_by_book_id(book_id, pool).await;
+    let template = AddRuleTemplate {
+        id: book_id.to_string(),
+        categories: &tree_views(&cats),
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
@@ -0,0 +1,161 @@
+use super::{
+    common::{html_str_handler, HandlerResult},
+    templates::{
+        MemberBalanceView, SettlementView, ShareView, SplitBalanceTemplate, SplitMemberView,
+        SplitRecordTemplate, TransferView,
+    },
+};
+use crate::{
+    app::api::get_book_default_id,
+    database::{
+        model::{
+            book::Member,
+            split::{simplify, SplitMethod},
+        },
+        querier::{
+            book::{get_members, get_settings},
+            record::get_by_id,
+            split::{balances, get_by_record_id, get_settlements, get_shares},
+        },
+    },
+    utils::{format_rupiah, serve_empty},
+};
+use askama::Template;
+use hyper::{body::Incoming, header::LOCATION, Request, Response, StatusCode};
+use sqlx::PgPool;
+use ulid::Ulid;
+
+fn member_name(members: &[Member], account_id: Ulid, current: Ulid) -> String {
+    if account_id == current {
+        return "You".to_string();
+    }
+    match members.iter().find(|m| m.account_id == account_id) {
+        Some(m) => m.email.clone(),
+        None => "Former member".to_string(),
+    }
+}
+
+pub async fn page_splits(req: Request<Incoming>, pool: PgPool, account_id: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    let settings = get_settings(&pool, book_id).await;
+    let members = get_members(book_id, &pool).await;
+    let nets = balances(book_id, &pool).await;
+    let datas: Vec<MemberBalanceView> = members
+        .iter()
+        .map(|m| {
+            let net = nets
+                .iter()
+                .find(|b| b.account_id == m.account_id)
+                .map_or(0.0, |b| b.net);
+            MemberBalanceView {
+                name: member_name(&members, m.account_id, account_id),
+                net: format_rupiah(net.abs()),
+                is_owed: net > 0.0,
+                is_settled: net.abs() < 1.0,
+            }
+        })
+        .collect();
+    let transfers: Vec<TransferView> = simplify(&nets)
+        .iter()
+        .map(|t| TransferView {
+            from_account_id: t.from_account_id.to_string(),
+            to_account_id: t.to_account_id.to_string(),
+            from_name: member_name(&members, t.from_account_id, account_id),
+            to_name: member_name(&members, t.to_account_id, account_id),
+            amount: t.amount.to_string(),
+            amount_in_rupiah: format_rupiah(t.amount),
+        })
+        .collect();
+    let settlements: Vec<SettlementView> = get_settlements(book_id, 20, &pool)
+        .await
+        .iter()
+        .map(|s| SettlementView {
+            from_name: member_name(&members, s.from_account_id, account_id),
+            to_name: member_name(&members, s.to_account_id, account_id),
+            amount_in_rupiah: format_rupiah(s.amount),
+            formatted_date: settings.format(s.created_at, "%e %b %Y"),
+        })
+        .collect();
+    let template = SplitBalanceTemplate {
+        book_id: book_id.to_string(),
+        balances: &datas,
+        transfers: &transfers,
+        settlements: &settlements,
+    };
+    let html = template.render().expect("Should render markup");
+    html_str_handler(&html).await
+}
+
+pub async fn page_split_record(req: Request<Incoming>, pool: PgPool, id: Ulid) -> HandlerResult {
+    let book_id: Ulid;
+    {
+        let header = req.headers();
+        let id = get_book_default_id(header);
+        book_id = id.await.unwrap();
+    }
+    match get_by_id(id, pool.clone()).await {
+        Some(record) if record.book_id == book_id => {
+            let members = get_members(book_id, &pool).await;
+            let split = get_by_record_id(record.id, &pool).await;
+            let shares = match &split {
+                Some(s) => get_shares(s.id, &pool).await,
+                None => Vec::new(),
+            };
+            let method = split.as_ref().map_or(SplitMethod::Equal, |s| s.method);
+            let datas: Vec<SplitMemberView> = members
+                .iter()
+                .map(|m| {
+                    let share = shares.iter().find(|s| s.account_id == m.account_id);
+                    SplitMemberView {
+                        account_id: m.account_id.to_string(),
+                        email: m.email.clone(),
+                        is_included: split.is_none() || share.is_some(),
+                        value: match (share, method) {
+                            (Some(s), SplitMethod::Exact) => s.amount.to_string(),
+                            (Some(s), SplitMethod::Percentage) => {
+                                format!("{:.2}", s.amount / record.amount * 100.0)
+                            }
+                            _ => "".to_string(),
+                        },
+                    }
+                })
+                .collect();
+            let share_views: Vec<ShareView> = shares
+                .iter()
+                .map(|s| ShareView {
+                    email: members
+                        .iter()
+                        .find(|m| m.account_id == s.account_id)
+                        .map_or("Former member".to_string(), |m| m.email.clone()),
+                    amount_in_rupiah: format_rupiah(s.amount),
+                })
+                .collect();
+            let template = SplitRecordTemplate {
+                record_id: record.id.to_string(),
+                notes: record.notes,
+                amount_in_rupiah: format_rupiah(record.amount),
+                method: method.as_str().to_string(),
+                paid_by: split
+                    .as_ref()
+                    .map(|s| s.paid_by)
+                    .or(members.first().map(|m| m.account_id))
+                    .map_or("".to_string(), |id| id.to_string()),
+                is_split: split.is_some(),
+                members: &datas,
+                shares: &share_views,
+            };
+            let html = template.render().expect("Should render markup");
+            html_str_handler(&html).await
+        }
+        _ => Ok(Response::builder()
+            .status(StatusCode::SEE_OTHER)
+            .header(LOCATION, "/record")
+            .body(serve_empty())
+            .unwrap()),
+    }
+}
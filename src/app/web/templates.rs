@@ -1,11 +1,23 @@
 use askama::Template;
 use ulid::Ulid;
 
-use crate::database::model::{book::Book, category::Category, record::Record};
+use crate::database::model::{
+    book::{Book, Member, Role},
+    category::CategorySuggestion,
+    debt::DebtPayment,
+    goal::Contribution,
+    invitation::Invitation,
+    payee::Payee,
+    preset::Preset,
+    record::Record,
+    recurring::Recurring,
+};
 
 #[derive(Default, Template)]
 #[template(path = "register.html")]
-pub struct RegisterTemplate {}
+pub struct RegisterTemplate<'a> {
+    pub presets: &'a [Preset],
+}
 
 #[derive(Default, Template)]
 #[template(path = "login.html")]
@@ -21,51 +33,117 @@ pub struct NotFoundTemplate {}
 
 #[derive(Default, Template)]
 #[template(path = "dashboard.html")]
-pub struct DashboardTemplate {}
+pub struct DashboardTemplate<'a> {
+    pub goals: &'a [GoalView],
+    pub invitations: &'a [Invitation],
+}
 
 #[derive(Default, Template)]
 #[template(path = "book/create-book.html")]
-pub struct AddNewBookTemplate {
+pub struct AddNewBookTemplate<'a> {
     pub is_first_time: bool,
+    pub presets: &'a [Preset],
 }
 
 #[derive(Default, Template)]
-#[template(path = "book/add-book-owner.html")]
-pub struct AddBookOwnerTemplate {}
+#[template(path = "book/invite-people.html")]
+pub struct InvitePeopleTemplate {
+    pub book_id: String,
+}
 
 #[derive(Default, Template)]
 #[template(path = "book/books.html")]
 pub struct BookListsBookTemplate<'a> {
     pub books: &'a [Book],
+    pub current_id: String,
+}
+
+#[derive(Default, Template)]
+#[template(path = "book/book-switcher.html")]
+pub struct BookSwitcherTemplate<'a> {
+    pub books: &'a [Book],
+    pub current_id: String,
 }
 
 #[derive(Default, Template)]
 #[template(path = "book/edit-book.html")]
-pub struct EditBookTemplate {
+pub struct EditBookTemplate<'a> {
     pub id: String,
     pub name: String,
     pub description: String,
+    pub timezone: String,
+    pub month_start_day: u32,
     pub is_can_delete: bool,
+    // only owners see the role of each member as something they can change
+    pub is_owner: bool,
+    pub account_id: Ulid,
+    pub members: &'a [Member],
+    pub roles: &'a [Role],
+    pub invitations: &'a [Invitation],
+}
+
+// a category placed in the tree, `label` is the name indented by its depth
+#[derive(Clone)]
+pub struct CategoryTreeView {
+    pub id: Ulid,
+    pub name: String,
+    pub label: String,
+    pub depth: usize,
+    pub is_expense: bool,
+    pub color: String,
+    pub icon: String,
+    pub record_count: i64,
+    pub is_archived: bool,
 }
 
 #[derive(Default, Template)]
 #[template(path = "category/categories.html")]
 pub struct CategoryListsTemplate<'a> {
-    pub categories: &'a [Category],
+    pub categories: &'a [CategoryTreeView],
+    pub show_archived: bool,
+    pub archived_count: usize,
 }
 
 #[derive(Default, Template)]
 #[template(path = "category/create-category.html")]
-pub struct AddNewCategoryTemplate {
+pub struct AddNewCategoryTemplate<'a> {
     pub id: String,
+    pub parents: &'a [CategoryTreeView],
+    pub color: String,
+    pub icons: &'a [&'a str],
 }
 
 #[derive(Default, Template)]
 #[template(path = "category/edit-category.html")]
-pub struct EditCategoryTemplate {
+pub struct EditCategoryTemplate<'a> {
     pub id: String,
     pub name: String,
     pub description: String,
+    pub parent_id: String,
+    pub parents: &'a [CategoryTreeView],
+    pub is_expense: bool,
+    pub color: String,
+    pub icon: String,
+    pub icons: &'a [&'a str],
+    pub record_count: i64,
+    pub is_archived: bool,
+    pub merge_targets: &'a [CategoryTreeView],
+    pub replacements: &'a [CategoryTreeView],
+    pub version: i32,
+}
+
+// what a category looks like now, answered to an edit made on an older version
+#[derive(Default, Template)]
+#[template(path = "category/category-conflict.html")]
+pub struct CategoryConflictTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub is_expense: bool,
+    pub parent_name: String,
+    pub color: String,
+    pub icon: String,
+    pub version: i32,
 }
 
 pub struct RecordWithRupiah {
@@ -84,7 +162,8 @@ pub struct RecordListsTemplate<'a> {
 #[template(path = "record/create-record.html")]
 pub struct AddRecordTemplate<'a> {
     pub id: String,
-    pub categories: &'a [Category],
+    pub frequent: &'a [CategoryTreeView],
+    pub categories: &'a [CategoryTreeView],
 }
 
 #[derive(Default, Template)]
@@ -92,7 +171,342 @@ pub struct AddRecordTemplate<'a> {
 pub struct EditRecordTemplate<'a> {
     pub id: String,
     pub notes: String,
+    pub payee: String,
     pub category_id: Ulid,
     pub amount: f32,
-    pub categories: &'a [Category],
+    pub categories: &'a [CategoryTreeView],
+    pub version: i32,
+}
+
+// what a record looks like now, answered to an edit made on an older version
+#[derive(Default, Template)]
+#[template(path = "record/record-conflict.html")]
+pub struct RecordConflictTemplate {
+    pub id: String,
+    pub notes: String,
+    pub payee: String,
+    pub amount_in_rupiah: String,
+    pub category_name: String,
+    pub version: i32,
+}
+
+#[derive(Default, Template)]
+#[template(path = "record/category-suggestions.html")]
+pub struct CategorySuggestionsTemplate<'a> {
+    pub suggestions: &'a [CategorySuggestion],
+}
+
+pub struct CategoryShareView {
+    pub category_id: String,
+    pub name: String,
+    pub color: String,
+    pub icon: String,
+    pub amount_in_rupiah: String,
+    pub percentage: String,
+    pub record_count: i64,
+}
+
+#[derive(Default, Template)]
+#[template(path = "report/report.html")]
+pub struct ReportTemplate<'a> {
+    pub period: String,
+    pub from: String,
+    pub to: String,
+    pub query: String,
+    pub previous_query: String,
+    pub next_query: String,
+    pub total_income: String,
+    pub total_expense: String,
+    pub net: String,
+    pub is_net_negative: bool,
+    pub incomes: &'a [CategoryShareView],
+    pub expenses: &'a [CategoryShareView],
+}
+
+#[derive(Default, Template)]
+#[template(path = "report/report-category.html")]
+pub struct ReportCategoryTemplate<'a> {
+    pub name: String,
+    pub is_expense: bool,
+    pub from: String,
+    pub to: String,
+    pub query: String,
+    pub total: String,
+    pub records: &'a [RecordWithRupiah],
+}
+
+pub struct BookTotalView {
+    pub name: String,
+    pub total_income: String,
+    pub total_expense: String,
+    pub net: String,
+    pub is_net_negative: bool,
+}
+
+#[derive(Default, Template)]
+#[template(path = "report/overview.html")]
+pub struct OverviewTemplate<'a> {
+    pub period: String,
+    pub from: String,
+    pub to: String,
+    pub previous_query: String,
+    pub next_query: String,
+    pub total_income: String,
+    pub total_expense: String,
+    pub net: String,
+    pub is_net_negative: bool,
+    pub books: &'a [BookTotalView],
+    pub records: &'a [RecordWithRupiah],
+}
+
+pub struct CategoryTrendView {
+    pub name: String,
+    pub is_expense: bool,
+    pub totals: Vec<String>,
+    pub change: String,
+    pub is_increase: bool,
+    pub change_percentage: String,
+    pub is_highlighted: bool,
+}
+
+#[derive(Default, Template)]
+#[template(path = "report/trend.html")]
+pub struct TrendTemplate<'a> {
+    pub bucket: String,
+    pub count: usize,
+    pub labels: Vec<String>,
+    pub categories: &'a [CategoryTrendView],
+    pub highlights: &'a [&'a CategoryTrendView],
+}
+
+pub struct RecurringWithRupiah {
+    pub recurring: Recurring,
+    pub amount_in_rupiah: String,
+}
+
+#[derive(Default, Template)]
+#[template(path = "recurring/recurrings.html")]
+pub struct RecurringListsTemplate<'a> {
+    pub recurrings: &'a [RecurringWithRupiah],
+}
+
+#[derive(Default, Template)]
+#[template(path = "recurring/create-recurring.html")]
+pub struct AddRecurringTemplate<'a> {
+    pub id: String,
+    pub categories: &'a [CategoryTreeView],
+}
+
+pub struct PayeeView {
+    pub id: String,
+    pub name: String,
+    pub default_category_id: String,
+    pub amount_in_rupiah: String,
+    pub record_count: i64,
+}
+
+#[derive(Default, Template)]
+#[template(path = "payee/payees.html")]
+pub struct PayeeListsTemplate<'a> {
+    pub from: String,
+    pub to: String,
+    pub previous_query: String,
+    pub next_query: String,
+    pub payees: &'a [PayeeView],
+    pub categories: &'a [CategoryTreeView],
+}
+
+#[derive(Default, Template)]
+#[template(path = "payee/payee-options.html")]
+pub struct PayeeOptionsTemplate<'a> {
+    pub payees: &'a [Payee],
+}
+
+pub struct RuleView {
+    pub id: String,
+    pub condition: String,
+    pub category_name: String,
+}
+
+#[derive(Default, Template)]
+#[template(path = "rule/rules.html")]
+pub struct RuleListsTemplate<'a> {
+    pub rules: &'a [RuleView],
+}
+
+#[derive(Default, Template)]
+#[template(path = "rule/create-rule.html")]
+pub struct AddRuleTemplate<'a> {
+    pub id: String,
+    pub categories: &'a [CategoryTreeView],
+}
+
+pub struct ForecastLineView {
+    pub date: String,
+    pub name: String,
+    pub amount_in_rupiah: String,
+    pub is_expense: bool,
+    pub is_estimate: bool,
+}
+
+pub struct ForecastMonthView {
+    pub label: String,
+    pub income: String,
+    pub expense: String,
+    pub closing_balance: String,
+    pub is_negative: bool,
+    pub lines: Vec<ForecastLineView>,
+}
+
+#[derive(Default, Template)]
+#[template(path = "report/forecast.html")]
+pub struct ForecastTemplate<'a> {
+    pub months: u32,
+    pub opening_balance: String,
+    pub is_opening_negative: bool,
+    pub forecasts: &'a [ForecastMonthView],
+}
+
+pub struct GoalView {
+    pub id: String,
+    pub name: String,
+    pub saved: String,
+    pub target_amount: String,
+    pub target_date: String,
+    pub progress: String,
+    pub required_monthly: String,
+    pub projected_completion: String,
+    pub is_reached: bool,
+    pub is_behind: bool,
+}
+
+pub struct ContributionWithRupiah {
+    pub contribution: Contribution,
+    pub amount_in_rupiah: String,
+    pub formatted_date: String,
+}
+
+#[derive(Default, Template)]
+#[template(path = "goal/goals.html")]
+pub struct GoalListsTemplate<'a> {
+    pub goals: &'a [GoalView],
+}
+
+#[derive(Default, Template)]
+#[template(path = "goal/create-goal.html")]
+pub struct AddGoalTemplate {
+    pub id: String,
+}
+
+#[derive(Template)]
+#[template(path = "goal/goal.html")]
+pub struct GoalDetailTemplate<'a> {
+    pub goal: GoalView,
+    pub contributions: &'a [ContributionWithRupiah],
+    pub records: &'a [Record],
+}
+
+pub struct DebtView {
+    pub id: String,
+    pub counterparty: String,
+    pub notes: String,
+    pub principal: String,
+    pub paid: String,
+    pub outstanding: String,
+    pub is_receivable: bool,
+    pub is_settled: bool,
+    pub overdue_count: usize,
+}
+
+pub struct InstallmentView {
+    pub due_date: String,
+    pub amount_in_rupiah: String,
+    pub is_paid: bool,
+    pub is_overdue: bool,
+}
+
+pub struct DebtPaymentWithRupiah {
+    pub payment: DebtPayment,
+    pub amount_in_rupiah: String,
+    pub formatted_date: String,
+}
+
+#[derive(Default, Template)]
+#[template(path = "debt/debts.html")]
+pub struct DebtListsTemplate<'a> {
+    pub receivable: String,
+    pub payable: String,
+    pub debts: &'a [DebtView],
+}
+
+#[derive(Default, Template)]
+#[template(path = "debt/create-debt.html")]
+pub struct AddDebtTemplate {
+    pub id: String,
+}
+
+#[derive(Template)]
+#[template(path = "debt/debt.html")]
+pub struct DebtDetailTemplate<'a> {
+    pub debt: DebtView,
+    pub installments: &'a [InstallmentView],
+    pub payments: &'a [DebtPaymentWithRupiah],
+    pub records: &'a [Record],
+}
+
+pub struct SplitMemberView {
+    pub account_id: String,
+    pub email: String,
+    pub is_included: bool,
+    pub value: String,
+}
+
+pub struct ShareView {
+    pub email: String,
+    pub amount_in_rupiah: String,
+}
+
+#[derive(Template)]
+#[template(path = "split/split-record.html")]
+pub struct SplitRecordTemplate<'a> {
+    pub record_id: String,
+    pub notes: String,
+    pub amount_in_rupiah: String,
+    pub method: String,
+    pub paid_by: String,
+    pub is_split: bool,
+    pub members: &'a [SplitMemberView],
+    pub shares: &'a [ShareView],
+}
+
+pub struct MemberBalanceView {
+    pub name: String,
+    pub net: String,
+    pub is_owed: bool,
+    pub is_settled: bool,
+}
+
+pub struct TransferView {
+    pub from_account_id: String,
+    pub to_account_id: String,
+    pub from_name: String,
+    pub to_name: String,
+    pub amount: String,
+    pub amount_in_rupiah: String,
+}
+
+pub struct SettlementView {
+    pub from_name: String,
+    pub to_name: String,
+    pub amount_in_rupiah: String,
+    pub formatted_date: String,
+}
+
+#[derive(Default, Template)]
+#[template(path = "split/splits.html")]
+pub struct SplitBalanceTemplate<'a> {
+    pub book_id: String,
+    pub balances: &'a [MemberBalanceView],
+    pub transfers: &'a [TransferView],
+    pub settlements: &'a [SettlementView],
 }
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS recurrings (
+    id BYTEA PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    amount FLOAT4 NOT NULL,
+    notes VARCHAR(255) NOT NULL,
+    day_of_month INTEGER NOT NULL DEFAULT 1,
+    category_id BYTEA REFERENCES categories(id) ON DELETE CASCADE,
+    book_id BYTEA REFERENCES books(id) ON DELETE CASCADE
+);
@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS goals (
+    id BYTEA PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    name VARCHAR(50) NOT NULL,
+    target_amount FLOAT4 NOT NULL,
+    target_date DATE NOT NULL,
+    book_id BYTEA REFERENCES books(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS goal_contributions (
+    id BYTEA PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMPTZ,
+    amount FLOAT4 NOT NULL,
+    notes VARCHAR(255) NOT NULL,
+    goal_id BYTEA REFERENCES goals(id) ON DELETE CASCADE,
+    record_id BYTEA REFERENCES records(id) ON DELETE CASCADE
+);
+
+-- a record counts towards a goal once, linking it again would count it twice
+CREATE UNIQUE INDEX IF NOT EXISTS goal_contributions_record
+    ON goal_contributions (goal_id, record_id) WHERE deleted_at IS NULL;
@@ -0,0 +1,30 @@
+CREATE TABLE IF NOT EXISTS debts (
+    id BYTEA PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    counterparty VARCHAR(50) NOT NULL,
+    notes VARCHAR(255) NOT NULL,
+    principal FLOAT4 NOT NULL,
+    is_receivable BOOLEAN NOT NULL DEFAULT FALSE,
+    book_id BYTEA REFERENCES books(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS debt_installments (
+    id BYTEA PRIMARY KEY,
+    due_date DATE NOT NULL,
+    amount FLOAT4 NOT NULL,
+    debt_id BYTEA REFERENCES debts(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS debt_payments (
+    id BYTEA PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMPTZ,
+    debt_id BYTEA REFERENCES debts(id) ON DELETE CASCADE,
+    record_id BYTEA REFERENCES records(id) ON DELETE CASCADE
+);
+
+-- a record pays towards a debt once, linking it again would count it twice
+CREATE UNIQUE INDEX IF NOT EXISTS debt_payments_record
+    ON debt_payments (debt_id, record_id) WHERE deleted_at IS NULL;
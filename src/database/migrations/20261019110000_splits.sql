@@ -0,0 +1,25 @@
+CREATE TABLE IF NOT EXISTS record_splits (
+    id BYTEA PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMPTZ,
+    method VARCHAR(10) NOT NULL,
+    paid_by BYTEA REFERENCES accounts(id),
+    record_id BYTEA REFERENCES records(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS record_split_shares (
+    split_id BYTEA REFERENCES record_splits(id) ON DELETE CASCADE,
+    account_id BYTEA REFERENCES accounts(id),
+    amount FLOAT4 NOT NULL,
+    PRIMARY KEY (split_id, account_id)
+);
+
+CREATE TABLE IF NOT EXISTS settlements (
+    id BYTEA PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMPTZ,
+    amount FLOAT4 NOT NULL,
+    from_account_id BYTEA REFERENCES accounts(id),
+    to_account_id BYTEA REFERENCES accounts(id),
+    book_id BYTEA REFERENCES books(id) ON DELETE CASCADE
+);
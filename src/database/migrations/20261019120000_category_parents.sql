@@ -0,0 +1 @@
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS parent_id BYTEA REFERENCES categories(id);
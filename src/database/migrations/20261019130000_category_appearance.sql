@@ -0,0 +1,2 @@
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS color VARCHAR(7) NOT NULL DEFAULT '#64748b';
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS icon VARCHAR(16) NOT NULL DEFAULT '';
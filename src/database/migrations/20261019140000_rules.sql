@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS rules (
+    id BYTEA PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    kind VARCHAR(16) NOT NULL,
+    pattern VARCHAR(255) NOT NULL DEFAULT '',
+    min_amount FLOAT4,
+    max_amount FLOAT4,
+    category_id BYTEA REFERENCES categories(id) ON DELETE CASCADE,
+    book_id BYTEA REFERENCES books(id) ON DELETE CASCADE
+);
+
+-- records whose category a rule picked, re-applying the rules only moves
+-- these and leaves categories chosen by hand alone
+ALTER TABLE records ADD COLUMN IF NOT EXISTS category_from_rule BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS payees (
+    id BYTEA PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    name VARCHAR(255) NOT NULL,
+    default_category_id BYTEA REFERENCES categories(id) ON DELETE SET NULL,
+    book_id BYTEA REFERENCES books(id) ON DELETE CASCADE
+);
+
+ALTER TABLE records ADD COLUMN IF NOT EXISTS payee_id BYTEA REFERENCES payees(id) ON DELETE SET NULL;
+
+-- one payee per name and book, saves racing each other land on the same row
+CREATE UNIQUE INDEX IF NOT EXISTS payees_book_name
+    ON payees (book_id, LOWER(name)) WHERE deleted_at IS NULL;
@@ -0,0 +1,3 @@
+-- every member could do anything before roles, so they all start as owners
+ALTER TABLE account_books ADD COLUMN IF NOT EXISTS role VARCHAR(16) NOT NULL DEFAULT 'owner';
+ALTER TABLE records ADD COLUMN IF NOT EXISTS created_by BYTEA;
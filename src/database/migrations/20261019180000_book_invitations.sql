@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS book_invitations (
+    id BYTEA PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    token VARCHAR(64) NOT NULL UNIQUE,
+    email VARCHAR(255) NOT NULL,
+    role VARCHAR(16) NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    expires_at TIMESTAMPTZ NOT NULL,
+    book_id BYTEA REFERENCES books(id) ON DELETE CASCADE,
+    invited_by BYTEA REFERENCES accounts(id)
+);
+
+CREATE INDEX IF NOT EXISTS book_invitations_email ON book_invitations (LOWER(email));
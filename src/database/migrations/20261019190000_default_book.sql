@@ -0,0 +1 @@
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS default_book_id BYTEA REFERENCES books(id) ON DELETE SET NULL;
@@ -0,0 +1,3 @@
+-- the zone a book counts its days in and the day its budgeting month starts on
+ALTER TABLE books ADD COLUMN IF NOT EXISTS timezone VARCHAR(64) NOT NULL DEFAULT 'UTC';
+ALTER TABLE books ADD COLUMN IF NOT EXISTS month_start_day SMALLINT NOT NULL DEFAULT 1;
@@ -0,0 +1,40 @@
+-- tells listeners on `book_changes` which book changed and what kind of row;
+-- the payload is the same for every row of a statement, so a bulk change is
+-- delivered once per transaction
+CREATE OR REPLACE FUNCTION notify_book_change() RETURNS trigger AS $$
+DECLARE
+    changed RECORD;
+    changed_book_id BYTEA;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+    IF TG_TABLE_NAME = 'books' THEN
+        changed_book_id := changed.id;
+    ELSE
+        changed_book_id := changed.book_id;
+    END IF;
+    PERFORM pg_notify(
+        'book_changes',
+        json_build_object('book_id', encode(changed_book_id, 'hex'), 'kind', TG_ARGV[0])::text
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS records_notify_book_change ON records;
+CREATE TRIGGER records_notify_book_change
+    AFTER INSERT OR UPDATE OR DELETE ON records
+    FOR EACH ROW EXECUTE FUNCTION notify_book_change('record');
+
+DROP TRIGGER IF EXISTS categories_notify_book_change ON categories;
+CREATE TRIGGER categories_notify_book_change
+    AFTER INSERT OR UPDATE OR DELETE ON categories
+    FOR EACH ROW EXECUTE FUNCTION notify_book_change('category');
+
+DROP TRIGGER IF EXISTS books_notify_book_change ON books;
+CREATE TRIGGER books_notify_book_change
+    AFTER INSERT OR UPDATE OR DELETE ON books
+    FOR EACH ROW EXECUTE FUNCTION notify_book_change('book');
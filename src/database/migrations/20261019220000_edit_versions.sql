@@ -0,0 +1,21 @@
+-- every change to a record or a category moves its version on; edit forms send
+-- back the version they were opened with so a stale edit can be refused
+ALTER TABLE records ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+
+CREATE OR REPLACE FUNCTION bump_version() RETURNS trigger AS $$
+BEGIN
+    NEW.version := OLD.version + 1;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS records_bump_version ON records;
+CREATE TRIGGER records_bump_version
+    BEFORE UPDATE ON records
+    FOR EACH ROW EXECUTE FUNCTION bump_version();
+
+DROP TRIGGER IF EXISTS categories_bump_version ON categories;
+CREATE TRIGGER categories_bump_version
+    BEFORE UPDATE ON categories
+    FOR EACH ROW EXECUTE FUNCTION bump_version();
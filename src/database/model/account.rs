@@ -84,18 +84,10 @@ impl FromRow<'_, PgRow> for Account {
         let id: [u8; 16] = row.get("id");
         let password: String = row.get("password");
         let created_at: DateTime<Utc> = row.get("created_at");
-        let updated_at: Option<DateTime<Utc>> = match row.try_get("updated_at") {
-            Ok(v) => v,
-            Err(_) => None,
-        };
-        let deleted_at: Option<DateTime<Utc>> = match row.try_get("deleted_at") {
-            Ok(v) => v,
-            Err(_) => None,
-        };
-        let email_verified_at: Option<DateTime<Utc>> = match row.try_get("email_verified_at") {
-            Ok(v) => v,
-            Err(_) => None,
-        };
+        let updated_at: Option<DateTime<Utc>> = row.try_get("updated_at").unwrap_or_default();
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
+        let email_verified_at: Option<DateTime<Utc>> =
+            row.try_get("email_verified_at").unwrap_or_default();
 
         let res: Account = Self {
             id: Ulid::from_bytes(id),
@@ -1,5 +1,6 @@
 use chrono::serde::ts_milliseconds;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgRow;
 use sqlx::prelude::FromRow;
@@ -12,6 +13,8 @@ pub struct Book {
     pub id: Ulid,
     pub name: String,
     pub description: String,
+    pub timezone: String,
+    pub month_start_day: u32,
 
     #[serde(with = "ts_milliseconds")]
     pub created_at: DateTime<Utc>,
@@ -33,6 +36,8 @@ impl Book {
             id,
             name: (&name).to_string(),
             description: (&desc).to_string(),
+            timezone: "UTC".to_string(),
+            month_start_day: 1,
             created_at,
             updated_at: None,
             deleted_at: None,
@@ -46,6 +51,10 @@ impl Book {
             description: book.description,
         }
     }
+
+    pub fn settings(&self) -> BookSettings {
+        BookSettings::new(&self.timezone, self.month_start_day)
+    }
 }
 
 impl FromRow<'_, PgRow> for Book {
@@ -53,15 +62,11 @@ impl FromRow<'_, PgRow> for Book {
         let id: [u8; 16] = row.get("id");
         let name: String = row.get("name");
         let description: String = row.get("description");
+        let timezone: String = row.try_get("timezone").unwrap_or("UTC".to_string());
+        let month_start_day: i16 = row.try_get("month_start_day").unwrap_or(1);
         let created_at: DateTime<Utc> = row.get("created_at");
-        let updated_at: Option<DateTime<Utc>> = match row.try_get("updated_at") {
-            Ok(v) => v,
-            Err(_) => None,
-        };
-        let deleted_at: Option<DateTime<Utc>> = match row.try_get("deleted_at") {
-            Ok(v) => v,
-            Err(_) => None,
-        };
+        let updated_at: Option<DateTime<Utc>> = row.try_get("updated_at").unwrap_or_default();
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
 
         let res: Book = Self {
             id: Ulid::from_bytes(id),
@@ -70,11 +75,79 @@ impl FromRow<'_, PgRow> for Book {
             deleted_at,
             name,
             description,
+            timezone,
+            month_start_day: month_start_day as u32,
         };
         Ok(res)
     }
 }
 
+// the budgeting month may start on any day that every month has
+pub const MAX_MONTH_START_DAY: u32 = 28;
+
+pub fn parse_timezone(s: &str) -> Option<Tz> {
+    s.trim().parse::<Tz>().ok()
+}
+
+// how a book reads time: the zone its days are counted in and the day of the
+// month its budgeting month starts on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BookSettings {
+    pub timezone: Tz,
+    pub month_start_day: u32,
+}
+
+impl Default for BookSettings {
+    fn default() -> Self {
+        Self {
+            timezone: Tz::UTC,
+            month_start_day: 1,
+        }
+    }
+}
+
+impl BookSettings {
+    // unknown zones fall back to UTC and out of range days to the 1st
+    pub fn new(timezone: &str, month_start_day: u32) -> Self {
+        Self {
+            timezone: parse_timezone(timezone).unwrap_or(Tz::UTC),
+            month_start_day: if (1..=MAX_MONTH_START_DAY).contains(&month_start_day) {
+                month_start_day
+            } else {
+                1
+            },
+        }
+    }
+
+    pub fn local_date(&self, at: DateTime<Utc>) -> NaiveDate {
+        at.with_timezone(&self.timezone).date_naive()
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.local_date(Utc::now())
+    }
+
+    pub fn format(&self, at: DateTime<Utc>, fmt: &str) -> String {
+        at.with_timezone(&self.timezone).format(fmt).to_string()
+    }
+
+    // the moment a local day starts; days whose midnight is skipped by a
+    // daylight saving change start an hour later
+    pub fn midnight(&self, date: NaiveDate) -> DateTime<Utc> {
+        let start = date.and_hms_opt(0, 0, 0).unwrap();
+        self.timezone
+            .from_local_datetime(&start)
+            .earliest()
+            .or_else(|| {
+                self.timezone
+                    .from_local_datetime(&(start + Duration::hours(1)))
+                    .earliest()
+            })
+            .map(|d| d.with_timezone(&Utc))
+            .unwrap_or_else(|| Utc.from_utc_datetime(&start))
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct AccountBook {
     #[serde(with = "ulid_as_u128")]
@@ -82,3 +155,82 @@ pub struct AccountBook {
     #[serde(with = "ulid_as_u128")]
     pub book_id: Ulid,
 }
+
+// what a member may do in a book; owners run the book itself, editors keep
+// its categories and other settings, contributors add records and change only
+// their own, viewers only read
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    Owner,
+    Editor,
+    Contributor,
+    Viewer,
+}
+
+pub static ROLES: [Role; 4] = [Role::Owner, Role::Editor, Role::Contributor, Role::Viewer];
+
+impl Role {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "owner" => Some(Role::Owner),
+            "editor" => Some(Role::Editor),
+            "contributor" => Some(Role::Contributor),
+            "viewer" => Some(Role::Viewer),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Editor => "editor",
+            Role::Contributor => "contributor",
+            Role::Viewer => "viewer",
+        }
+    }
+
+    // editing or deleting the book and deciding who is in it
+    pub fn can_manage(&self) -> bool {
+        *self == Role::Owner
+    }
+
+    // categories, rules, payees, goals, debts and recurring items
+    pub fn can_organize(&self) -> bool {
+        matches!(self, Role::Owner | Role::Editor)
+    }
+
+    pub fn can_add_records(&self) -> bool {
+        *self != Role::Viewer
+    }
+
+    // records written before roles existed have no author and are left to
+    // owners and editors
+    pub fn can_change_record(&self, created_by: Option<Ulid>, account_id: Ulid) -> bool {
+        match self {
+            Role::Owner | Role::Editor => true,
+            Role::Contributor => created_by == Some(account_id),
+            Role::Viewer => false,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Member {
+    #[serde(with = "ulid_as_u128")]
+    pub account_id: Ulid,
+    pub email: String,
+    pub role: Role,
+}
+
+impl FromRow<'_, PgRow> for Member {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let account_id: [u8; 16] = row.get("account_id");
+        let role: String = row.get("role");
+        let res: Member = Self {
+            account_id: Ulid::from_bytes(account_id),
+            email: row.get("email"),
+            role: Role::parse(&role).unwrap_or(Role::Viewer),
+        };
+        Ok(res)
+    }
+}
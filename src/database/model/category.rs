@@ -4,17 +4,43 @@ use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgRow;
 use sqlx::prelude::FromRow;
 use sqlx::Row;
+use std::collections::HashMap;
 use ulid::{serde::ulid_as_u128, Ulid};
 
+pub static DEFAULT_COLOR: &str = "#64748b";
+
+// icons a category can pick from, the empty one shows no icon
+pub static ICONS: [&str; 16] = [
+    "", "🏠", "💡", "🚗", "🛒", "🍽️", "💊", "🎬", "💳", "💇", "💰", "🎓", "💼", "🎁", "📈", "📦",
+];
+
+// a colour in the `#rrggbb` form used by the colour picker
+pub fn is_valid_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Category {
     #[serde(with = "ulid_as_u128")]
     pub id: Ulid,
     #[serde(with = "ulid_as_u128")]
     pub book_id: Ulid,
+    pub parent_id: Option<Ulid>,
     pub name: String,
     pub description: String,
     pub is_expense: bool,
+    pub color: String,
+    pub icon: String,
+    // live records in the category, only filled by listing queries
+    pub record_count: i64,
+    // how often and how recently the category was used, only filled by
+    // `get_by_usage`
+    pub usage_score: f32,
+    // an archived category keeps its records but is no longer offered when
+    // writing new ones
+    pub archived_at: Option<DateTime<Utc>>,
+    // moves on with every change, see `querier::category::edit`
+    pub version: i32,
 
     #[serde(with = "ts_milliseconds")]
     pub created_at: DateTime<Utc>,
@@ -23,7 +49,13 @@ pub struct Category {
 }
 
 impl Category {
-    pub fn new(name: &str, desc: &str, is_expense: bool, book_id: Ulid) -> Self {
+    pub fn new(
+        name: &str,
+        desc: &str,
+        is_expense: bool,
+        book_id: Ulid,
+        parent_id: Option<Ulid>,
+    ) -> Self {
         let id = ulid::Ulid::new();
         let created_at = chrono::offset::Utc::now();
         Self {
@@ -34,87 +66,107 @@ impl Category {
             updated_at: None,
             deleted_at: None,
             book_id,
+            parent_id,
             is_expense,
+            color: DEFAULT_COLOR.to_string(),
+            icon: "".to_string(),
+            record_count: 0,
+            usage_score: 0.0,
+            archived_at: None,
+            version: 1,
+        }
+    }
+
+    // true when making `parent_id` the parent of this category would put the
+    // category inside its own subtree
+    pub fn would_cycle(&self, parent_id: Ulid, categories: &[Category]) -> bool {
+        let mut current = Some(parent_id);
+        // a tree never has more levels than categories
+        for _ in 0..=categories.len() {
+            match current {
+                Some(id) if id == self.id => return true,
+                Some(id) => {
+                    current = categories
+                        .iter()
+                        .find(|c| c.id == id)
+                        .and_then(|c| c.parent_id)
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
+// orders categories depth-first with the depth of every category; a category
+// whose parent is missing from the list is shown at the top level
+pub fn tree(categories: &[Category]) -> Vec<(Category, usize)> {
+    fn walk(
+        parent_id: Option<Ulid>,
+        depth: usize,
+        categories: &[Category],
+        roots: &[&Category],
+        datas: &mut Vec<(Category, usize)>,
+    ) {
+        let children: Vec<&Category> = match parent_id {
+            Some(id) => categories
+                .iter()
+                .filter(|c| c.parent_id == Some(id))
+                .collect(),
+            None => roots.to_vec(),
+        };
+        for child in children {
+            datas.push((child.clone(), depth));
+            walk(Some(child.id), depth + 1, categories, roots, datas);
         }
     }
 
-    pub fn batch(book_id: Ulid) -> Vec<Self> {
-        let predefined_categories = [
-            ("Salary/Wages", "Your regular income from your job.", false),
-            (
-                "Freelance/Contract Work",
-                "Income from any freelance or contract work you may do.",
-                false,
-            ),
-            (
-                "Side Hustle",
-                "Income from any side businesses or projects you're involved in.",
-                false,
-            ),
-            (
-                "Investment Income",
-                "Dividends, interest, or other income generated from investments.",
-                false,
-            ),
-            (
-                "Gifts/Donations",
-                "Any money received as gifts or donations.",
-                false,
-            ),
-            (
-                "Rental Income",
-                "If you earn income from renting out property.",
-                false,
-            ),
-            ("Rent/Mortgage", "Monthly rent", true),
-            ("Property Taxes", "Property tax payments", true),
-            ("Home Insurance", "Insurance for your home", true),
-            ("Electricity", "Monthly electricity bill", true),
-            ("Water", "Monthly water bill", true),
-            ("Gas", "Monthly gas bill", true),
-            ("Internet/Phone", "Internet and phone bills", true),
-            ("Car Payment", "Monthly car loan payment", true),
-            ("Gas", "Gas expenses for your vehicle", true),
-            ("Insurance", "Vehicle insurance", true),
-            ("Public Transportation", "Public transportation costs", true),
-            ("Food", "Grocery expenses", true),
-            ("Household Supplies", "Expenses for household items", true),
-            ("Health Insurance", "Health insurance premiums", true),
-            ("Medications", "Costs for medications", true),
-            ("Doctor's Visits", "Medical check-up expenses", true),
-            ("Dining Out", "Expenses for dining out", true),
-            ("Movies", "Entertainment expenses for movies", true),
-            ("Subscriptions", "(Netflix, Spotify, etc.)", true),
-            (
-                "Credit Card Payments",
-                "Payments towards credit card balances",
-                true,
-            ),
-            ("Loan Payments", "Monthly loan payments", true),
-            ("Haircuts", "Cost of haircuts", true),
-            ("Toiletries", "Expenses for toiletries", true),
-            (
-                "Emergency Fund Contributions",
-                "Contributions to your emergency fund",
-                true,
-            ),
-            ("Retirement Savings", "Savings for retirement", true),
-            ("Tuition", "Education tuition fees", true),
-            ("Books", "Costs for educational books", true),
-            ("Courses", "Expenses for additional courses", true),
-            (
-                "Miscellaneous",
-                "Any other expenses not covered in the above categories.",
-                true,
-            ),
-        ];
-
-        predefined_categories
-            .iter()
-            .map(|&(name, description, is_expense)| {
-                Self::new(name, description, is_expense, book_id)
-            })
-            .collect()
+    let roots: Vec<&Category> = categories
+        .iter()
+        .filter(|c| {
+            c.parent_id
+                .is_none_or(|p| !categories.iter().any(|other| other.id == p))
+        })
+        .collect();
+    let mut datas: Vec<(Category, usize)> = Vec::new();
+    walk(None, 0, categories, &roots, &mut datas);
+    datas
+}
+
+// orders categories by the usage of their whole subtree, so a parent whose
+// sub-categories are used a lot comes before one that is rarely used; `tree`
+// keeps this order between siblings
+pub fn sort_by_usage(categories: &mut [Category]) {
+    let scores: HashMap<Ulid, f32> = categories
+        .iter()
+        .map(|c| {
+            let score: f32 = categories
+                .iter()
+                .filter(|other| c.would_cycle(other.id, categories))
+                .map(|other| other.usage_score)
+                .sum();
+            (c.id, score)
+        })
+        .collect();
+    categories.sort_by(|a, b| scores[&b.id].total_cmp(&scores[&a.id]));
+}
+
+// a category suggested for a record from the notes of earlier records
+#[derive(Clone, Debug)]
+pub struct CategorySuggestion {
+    pub category_id: Ulid,
+    pub name: String,
+    pub record_count: i64,
+}
+
+impl FromRow<'_, PgRow> for CategorySuggestion {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let category_id: [u8; 16] = row.get("category_id");
+        Ok(Self {
+            category_id: Ulid::from_bytes(category_id),
+            name: row.get("name"),
+            record_count: row.get("record_count"),
+        })
     }
 }
 
@@ -122,18 +174,13 @@ impl FromRow<'_, PgRow> for Category {
     fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
         let id: [u8; 16] = row.get("id");
         let book_id: [u8; 16] = row.get("book_id");
+        let parent_id: Option<[u8; 16]> = row.try_get("parent_id").unwrap_or_default();
         let is_expense: bool = row.get("is_expense");
         let name: String = row.get("name");
         let description: String = row.get("description");
         let created_at: DateTime<Utc> = row.get("created_at");
-        let updated_at: Option<DateTime<Utc>> = match row.try_get("updated_at") {
-            Ok(v) => v,
-            Err(_) => None,
-        };
-        let deleted_at: Option<DateTime<Utc>> = match row.try_get("deleted_at") {
-            Ok(v) => v,
-            Err(_) => None,
-        };
+        let updated_at: Option<DateTime<Utc>> = row.try_get("updated_at").unwrap_or_default();
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
 
         let res: Category = Self {
             id: Ulid::from_bytes(id),
@@ -143,8 +190,76 @@ impl FromRow<'_, PgRow> for Category {
             name,
             description,
             book_id: Ulid::from_bytes(book_id),
+            parent_id: parent_id.map(Ulid::from_bytes),
             is_expense,
+            color: row.get("color"),
+            icon: row.get("icon"),
+            record_count: row.try_get("record_count").unwrap_or_default(),
+            usage_score: row.try_get("usage_score").unwrap_or_default(),
+            archived_at: row.try_get("archived_at").unwrap_or_default(),
+            version: row.try_get("version").unwrap_or_default(),
         };
         Ok(res)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn child(name: &str, parent: &Category) -> Category {
+        Category::new(name, "", true, parent.book_id, Some(parent.id))
+    }
+
+    #[test]
+    fn a_category_cannot_be_its_own_parent() {
+        let food = Category::new("Food", "", true, Ulid::new(), None);
+        assert!(food.would_cycle(food.id, std::slice::from_ref(&food)));
+    }
+
+    #[test]
+    fn a_category_cannot_move_under_its_descendants() {
+        let food = Category::new("Food", "", true, Ulid::new(), None);
+        let snacks = child("Snacks", &food);
+        let chips = child("Chips", &snacks);
+        let categories = vec![food.clone(), snacks.clone(), chips.clone()];
+        assert!(food.would_cycle(snacks.id, &categories));
+        assert!(food.would_cycle(chips.id, &categories));
+        assert!(!chips.would_cycle(food.id, &categories));
+        assert!(!snacks.would_cycle(food.id, &categories));
+    }
+
+    #[test]
+    fn unrelated_parents_are_allowed() {
+        let book_id = Ulid::new();
+        let food = Category::new("Food", "", true, book_id, None);
+        let home = Category::new("Home", "", true, book_id, None);
+        let rent = child("Rent", &home);
+        let categories = vec![food.clone(), home.clone(), rent.clone()];
+        assert!(!food.would_cycle(rent.id, &categories));
+        assert!(!food.would_cycle(Ulid::new(), &categories));
+    }
+
+    #[test]
+    fn a_looping_tree_is_refused_instead_of_walked_forever() {
+        let book_id = Ulid::new();
+        let food = Category::new("Food", "", true, book_id, None);
+        let mut a = Category::new("A", "", true, book_id, None);
+        let mut b = Category::new("B", "", true, book_id, None);
+        a.parent_id = Some(b.id);
+        b.parent_id = Some(a.id);
+        let categories = vec![food.clone(), a.clone(), b];
+        assert!(food.would_cycle(a.id, &categories));
+    }
+
+    #[test]
+    fn tree_lists_children_under_their_parent() {
+        let food = Category::new("Food", "", true, Ulid::new(), None);
+        let snacks = child("Snacks", &food);
+        let orphan = Category::new("Orphan", "", true, food.book_id, Some(Ulid::new()));
+        let ordered = tree(&[snacks.clone(), orphan.clone(), food.clone()]);
+        let names: Vec<(&str, usize)> =
+            ordered.iter().map(|(c, d)| (c.name.as_str(), *d)).collect();
+        assert_eq!(names, vec![("Orphan", 0), ("Food", 0), ("Snacks", 1)]);
+    }
+}
@@ -0,0 +1,272 @@
+use chrono::serde::ts_milliseconds;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::prelude::FromRow;
+use sqlx::Row;
+use ulid::{serde::ulid_as_u128, Ulid};
+
+use super::report::add_months;
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Debt {
+    #[serde(with = "ulid_as_u128")]
+    pub id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub book_id: Ulid,
+    pub counterparty: String,
+    pub notes: String,
+    pub principal: f32,
+    // true when the counterparty owes me, false when I owe the counterparty
+    pub is_receivable: bool,
+    pub paid: f32,
+
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Debt {
+    pub fn new(
+        counterparty: &str,
+        notes: &str,
+        principal: f32,
+        is_receivable: bool,
+        book_id: Ulid,
+    ) -> Self {
+        let id = ulid::Ulid::new();
+        let created_at = chrono::offset::Utc::now();
+        Self {
+            id,
+            book_id,
+            counterparty: counterparty.to_string(),
+            notes: notes.to_string(),
+            principal,
+            is_receivable,
+            paid: 0.0,
+            created_at,
+            updated_at: None,
+            deleted_at: None,
+        }
+    }
+
+    pub fn outstanding(&self) -> f32 {
+        (self.principal - self.paid).max(0.0)
+    }
+
+    // follows the linked records, so editing or deleting one reopens or
+    // settles the debt
+    pub fn is_settled(&self) -> bool {
+        self.paid >= self.principal
+    }
+
+    // installments are paid off in order, so an installment is covered once the
+    // payments reach the running total up to and including it
+    pub fn paid_installments(&self, installments: &[Installment]) -> usize {
+        let mut running = 0.0;
+        installments
+            .iter()
+            .take_while(|i| {
+                running += i.amount;
+                running <= self.paid
+            })
+            .count()
+    }
+
+    pub fn overdue_installments<'a>(
+        &self,
+        installments: &'a [Installment],
+        today: NaiveDate,
+    ) -> Vec<&'a Installment> {
+        installments
+            .iter()
+            .skip(self.paid_installments(installments))
+            .filter(|i| i.due_date < today)
+            .collect()
+    }
+}
+
+impl FromRow<'_, PgRow> for Debt {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let id: [u8; 16] = row.get("id");
+        let book_id: [u8; 16] = row.get("book_id");
+        let created_at: DateTime<Utc> = row.get("created_at");
+        let updated_at: Option<DateTime<Utc>> = row.try_get("updated_at").unwrap_or_default();
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
+
+        let res: Debt = Self {
+            id: Ulid::from_bytes(id),
+            book_id: Ulid::from_bytes(book_id),
+            counterparty: row.get("counterparty"),
+            notes: row.get("notes"),
+            principal: row.get("principal"),
+            is_receivable: row.get("is_receivable"),
+            paid: row.try_get("paid").unwrap_or_default(),
+            created_at,
+            updated_at,
+            deleted_at,
+        };
+        Ok(res)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Installment {
+    #[serde(with = "ulid_as_u128")]
+    pub id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub debt_id: Ulid,
+    pub due_date: NaiveDate,
+    pub amount: f32,
+}
+
+impl Installment {
+    // splits the principal into `count` monthly installments, the last one
+    // absorbing any rounding difference
+    pub fn schedule(debt_id: Ulid, principal: f32, count: u32, first_due: NaiveDate) -> Vec<Self> {
+        if count == 0 {
+            return Vec::new();
+        }
+        let amount = (principal / count as f32).floor();
+        (0..count)
+            .map(|i| {
+                let month = add_months(first_due, i as i32);
+                let due_date = month
+                    .with_day(first_due.day())
+                    .unwrap_or_else(|| add_months(first_due, i as i32 + 1).pred_opt().unwrap());
+                let amount = if i == count - 1 {
+                    principal - amount * (count - 1) as f32
+                } else {
+                    amount
+                };
+                Self {
+                    id: ulid::Ulid::new(),
+                    debt_id,
+                    due_date,
+                    amount,
+                }
+            })
+            .collect()
+    }
+}
+
+impl FromRow<'_, PgRow> for Installment {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let id: [u8; 16] = row.get("id");
+        let debt_id: [u8; 16] = row.get("debt_id");
+        let res: Installment = Self {
+            id: Ulid::from_bytes(id),
+            debt_id: Ulid::from_bytes(debt_id),
+            due_date: row.get("due_date"),
+            amount: row.get("amount"),
+        };
+        Ok(res)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct DebtPayment {
+    #[serde(with = "ulid_as_u128")]
+    pub id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub debt_id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub record_id: Ulid,
+    pub notes: String,
+    pub amount: f32,
+
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl DebtPayment {
+    pub fn new(debt_id: Ulid, record_id: Ulid) -> Self {
+        Self {
+            id: ulid::Ulid::new(),
+            debt_id,
+            record_id,
+            notes: "".to_string(),
+            amount: 0.0,
+            created_at: chrono::offset::Utc::now(),
+        }
+    }
+}
+
+impl FromRow<'_, PgRow> for DebtPayment {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let id: [u8; 16] = row.get("id");
+        let debt_id: [u8; 16] = row.get("debt_id");
+        let record_id: [u8; 16] = row.get("record_id");
+        let res: DebtPayment = Self {
+            id: Ulid::from_bytes(id),
+            debt_id: Ulid::from_bytes(debt_id),
+            record_id: Ulid::from_bytes(record_id),
+            notes: row.get("notes"),
+            amount: row.get("amount"),
+            created_at: row.get("created_at"),
+        };
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn schedule_gives_the_rounding_difference_to_the_last_installment() {
+        let debt_id = Ulid::new();
+        let installments = Installment::schedule(debt_id, 1000.0, 3, date(2024, 1, 10));
+        let amounts: Vec<f32> = installments.iter().map(|i| i.amount).collect();
+        assert_eq!(amounts, vec![333.0, 333.0, 334.0]);
+        assert!(installments.iter().all(|i| i.debt_id == debt_id));
+        let dates: Vec<NaiveDate> = installments.iter().map(|i| i.due_date).collect();
+        assert_eq!(
+            dates,
+            vec![date(2024, 1, 10), date(2024, 2, 10), date(2024, 3, 10)]
+        );
+    }
+
+    #[test]
+    fn schedule_clamps_due_days_to_shorter_months() {
+        let installments = Installment::schedule(Ulid::new(), 400.0, 4, date(2024, 11, 30));
+        let dates: Vec<NaiveDate> = installments.iter().map(|i| i.due_date).collect();
+        assert_eq!(
+            dates,
+            vec![
+                date(2024, 11, 30),
+                date(2024, 12, 30),
+                date(2025, 1, 30),
+                date(2025, 2, 28)
+            ]
+        );
+    }
+
+    #[test]
+    fn schedule_without_installments_is_empty() {
+        assert!(Installment::schedule(Ulid::new(), 400.0, 0, date(2024, 1, 1)).is_empty());
+    }
+
+    #[test]
+    fn payments_cover_installments_in_order() {
+        let mut debt = Debt::new("Budi", "", 900.0, false, Ulid::new());
+        let installments = Installment::schedule(debt.id, 900.0, 3, date(2024, 1, 10));
+        debt.paid = 500.0;
+        assert_eq!(debt.paid_installments(&installments), 1);
+        assert_eq!(debt.outstanding(), 400.0);
+        assert!(!debt.is_settled());
+        let overdue = debt.overdue_installments(&installments, date(2024, 3, 1));
+        assert_eq!(overdue.len(), 1);
+        assert_eq!(overdue[0].due_date, date(2024, 2, 10));
+
+        debt.paid = 950.0;
+        assert_eq!(debt.paid_installments(&installments), 3);
+        assert_eq!(debt.outstanding(), 0.0);
+        assert!(debt.is_settled());
+    }
+}
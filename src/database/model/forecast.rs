@@ -0,0 +1,182 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use ulid::Ulid;
+
+use super::{recurring::Recurring, report::CategoryTotal};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ForecastLine {
+    pub date: Option<NaiveDate>,
+    pub category_id: String,
+    pub name: String,
+    pub is_expense: bool,
+    pub amount: f32,
+    // false for a known upcoming item, true for an estimate from history
+    pub is_estimate: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ForecastMonth {
+    pub month: NaiveDate,
+    pub opening_balance: f32,
+    pub income: f32,
+    pub expense: f32,
+    pub closing_balance: f32,
+    pub lines: Vec<ForecastLine>,
+}
+
+impl ForecastMonth {
+    pub fn is_negative(&self) -> bool {
+        self.closing_balance < 0.0
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Forecast {
+    pub opening_balance: f32,
+    pub months: Vec<ForecastMonth>,
+}
+
+impl Forecast {
+    // projects the balance month by month. recurring items are known lines,
+    // every other category is estimated from its average over `history_months`
+    pub fn new(
+        opening_balance: f32,
+        months: &[NaiveDate],
+        recurrings: &[Recurring],
+        history: &[CategoryTotal],
+        history_months: u32,
+    ) -> Self {
+        let known: Vec<Ulid> = recurrings.iter().map(|r| r.category_id).collect();
+        let mut balance = opening_balance;
+        let mut datas: Vec<ForecastMonth> = Vec::new();
+        for month in months {
+            let mut lines: Vec<ForecastLine> = recurrings
+                .iter()
+                .map(|r| ForecastLine {
+                    date: Some(r.due_in(*month)),
+                    category_id: r.category_id.to_string(),
+                    name: r.notes.clone(),
+                    is_expense: r.is_expense,
+                    amount: r.amount,
+                    is_estimate: false,
+                })
+                .collect();
+            for total in history {
+                if known.contains(&total.category_id) || history_months == 0 {
+                    continue;
+                }
+                lines.push(ForecastLine {
+                    date: None,
+                    category_id: total.category_id.to_string(),
+                    name: total.name.clone(),
+                    is_expense: total.is_expense,
+                    amount: total.total / history_months as f32,
+                    is_estimate: true,
+                });
+            }
+            let income: f32 = lines
+                .iter()
+                .filter(|l| !l.is_expense)
+                .map(|l| l.amount)
+                .sum();
+            let expense: f32 = lines
+                .iter()
+                .filter(|l| l.is_expense)
+                .map(|l| l.amount)
+                .sum();
+            let opening = balance;
+            balance = opening + income - expense;
+            datas.push(ForecastMonth {
+                month: *month,
+                opening_balance: opening,
+                income,
+                expense,
+                closing_balance: balance,
+                lines,
+            });
+        }
+        Self {
+            opening_balance,
+            months: datas,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn history(category_id: Ulid, is_expense: bool, total: f32) -> CategoryTotal {
+        CategoryTotal {
+            category_id,
+            name: "History".to_string(),
+            is_expense,
+            color: "".to_string(),
+            icon: "".to_string(),
+            total,
+            record_count: 1,
+        }
+    }
+
+    #[test]
+    fn balance_rolls_over_from_month_to_month() {
+        let book_id = Ulid::new();
+        let mut salary = Recurring::new("salary", 1000.0, 25, book_id, Ulid::new());
+        salary.is_expense = false;
+        let rent = Recurring::new("rent", 600.0, 1, book_id, Ulid::new());
+        let months = [date(2024, 1, 1), date(2024, 2, 1)];
+        let forecast = Forecast::new(100.0, &months, &[salary, rent], &[], 3);
+        assert_eq!(forecast.months.len(), 2);
+        let first = &forecast.months[0];
+        assert_eq!(first.opening_balance, 100.0);
+        assert_eq!(first.income, 1000.0);
+        assert_eq!(first.expense, 600.0);
+        assert_eq!(first.closing_balance, 500.0);
+        assert_eq!(forecast.months[1].opening_balance, 500.0);
+        assert_eq!(forecast.months[1].closing_balance, 900.0);
+    }
+
+    #[test]
+    fn categories_without_recurring_items_are_averaged_from_history() {
+        let book_id = Ulid::new();
+        let rent_category = Ulid::new();
+        let rent = Recurring::new("rent", 600.0, 1, book_id, rent_category);
+        let groceries = Ulid::new();
+        let totals = [
+            history(rent_category, true, 1800.0),
+            history(groceries, true, 900.0),
+        ];
+        let forecast = Forecast::new(0.0, &[date(2024, 1, 1)], &[rent], &totals, 3);
+        let lines = &forecast.months[0].lines;
+        assert_eq!(lines.len(), 2);
+        let estimate = lines.iter().find(|l| l.is_estimate).unwrap();
+        assert_eq!(estimate.category_id, groceries.to_string());
+        assert_eq!(estimate.amount, 300.0);
+        assert_eq!(estimate.date, None);
+        assert_eq!(forecast.months[0].expense, 900.0);
+        assert!(forecast.months[0].is_negative());
+    }
+
+    #[test]
+    fn no_history_months_means_no_estimates() {
+        let totals = [history(Ulid::new(), true, 900.0)];
+        let forecast = Forecast::new(0.0, &[date(2024, 1, 1)], &[], &totals, 0);
+        assert!(forecast.months[0].lines.is_empty());
+    }
+
+    #[test]
+    fn recurring_items_fall_inside_the_budgeting_month() {
+        let item = |day| Recurring::new("x", 1.0, day, Ulid::new(), Ulid::new());
+        assert_eq!(item(31).due_in(date(2024, 2, 1)), date(2024, 2, 29));
+        assert_eq!(item(15).due_in(date(2024, 2, 1)), date(2024, 2, 15));
+        // months starting on the 25th run into the next calendar month
+        assert_eq!(item(28).due_in(date(2024, 1, 25)), date(2024, 1, 28));
+        assert_eq!(item(5).due_in(date(2024, 1, 25)), date(2024, 2, 5));
+        assert_eq!(item(31).due_in(date(2024, 2, 25)), date(2024, 2, 29));
+    }
+}
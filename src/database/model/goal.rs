@@ -0,0 +1,230 @@
+use chrono::serde::ts_milliseconds;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::prelude::FromRow;
+use sqlx::Row;
+use ulid::{serde::ulid_as_u128, Ulid};
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Goal {
+    #[serde(with = "ulid_as_u128")]
+    pub id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub book_id: Ulid,
+    pub name: String,
+    pub target_amount: f32,
+    pub target_date: NaiveDate,
+    pub saved: f32,
+
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+fn months_between(from: NaiveDate, to: NaiveDate) -> i32 {
+    let months = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32;
+    if to.day() < from.day() {
+        months - 1
+    } else {
+        months
+    }
+}
+
+impl Goal {
+    pub fn new(name: &str, target_amount: f32, target_date: NaiveDate, book_id: Ulid) -> Self {
+        let id = ulid::Ulid::new();
+        let created_at = chrono::offset::Utc::now();
+        Self {
+            id,
+            name: name.to_string(),
+            target_amount,
+            target_date,
+            saved: 0.0,
+            created_at,
+            updated_at: None,
+            deleted_at: None,
+            book_id,
+        }
+    }
+
+    pub fn remaining(&self) -> f32 {
+        (self.target_amount - self.saved).max(0.0)
+    }
+
+    pub fn is_reached(&self) -> bool {
+        self.saved >= self.target_amount
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.target_amount <= 0.0 {
+            100.0
+        } else {
+            (self.saved / self.target_amount * 100.0).min(100.0)
+        }
+    }
+
+    // amount to put aside every month to reach the target on the target date,
+    // the whole remaining amount once the date has passed
+    pub fn required_monthly(&self, today: NaiveDate) -> f32 {
+        let months = months_between(today, self.target_date).max(1);
+        self.remaining() / months as f32
+    }
+
+    // completion date if saving keeps the average pace since the goal was created
+    pub fn projected_completion(&self, today: NaiveDate) -> Option<NaiveDate> {
+        if self.is_reached() {
+            return Some(today);
+        }
+        let days = (today - self.created_at.date_naive()).num_days().max(1);
+        let daily = self.saved / days as f32;
+        if daily <= 0.0 {
+            return None;
+        }
+        let days_left = (self.remaining() / daily).ceil() as i64;
+        Some(today + Duration::days(days_left))
+    }
+}
+
+impl FromRow<'_, PgRow> for Goal {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let id: [u8; 16] = row.get("id");
+        let book_id: [u8; 16] = row.get("book_id");
+        let created_at: DateTime<Utc> = row.get("created_at");
+        let updated_at: Option<DateTime<Utc>> = row.try_get("updated_at").unwrap_or_default();
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
+
+        let res: Goal = Self {
+            id: Ulid::from_bytes(id),
+            book_id: Ulid::from_bytes(book_id),
+            name: row.get("name"),
+            target_amount: row.get("target_amount"),
+            target_date: row.get("target_date"),
+            saved: row.try_get("saved").unwrap_or_default(),
+            created_at,
+            updated_at,
+            deleted_at,
+        };
+        Ok(res)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Contribution {
+    #[serde(with = "ulid_as_u128")]
+    pub id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub goal_id: Ulid,
+    pub record_id: Option<Ulid>,
+    pub notes: String,
+    pub amount: f32,
+
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Contribution {
+    pub fn new(notes: &str, amount: f32, goal_id: Ulid, record_id: Option<Ulid>) -> Self {
+        let id = ulid::Ulid::new();
+        let created_at = chrono::offset::Utc::now();
+        Self {
+            id,
+            goal_id,
+            record_id,
+            notes: notes.to_string(),
+            amount,
+            created_at,
+            deleted_at: None,
+        }
+    }
+}
+
+impl FromRow<'_, PgRow> for Contribution {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let id: [u8; 16] = row.get("id");
+        let goal_id: [u8; 16] = row.get("goal_id");
+        let record_id: Option<[u8; 16]> = row.try_get("record_id").unwrap_or_default();
+        let created_at: DateTime<Utc> = row.get("created_at");
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
+
+        let res: Contribution = Self {
+            id: Ulid::from_bytes(id),
+            goal_id: Ulid::from_bytes(goal_id),
+            record_id: record_id.map(Ulid::from_bytes),
+            notes: row.get("notes"),
+            amount: row.get("amount"),
+            created_at,
+            deleted_at,
+        };
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn goal(target_amount: f32, target_date: NaiveDate, saved: f32, created: NaiveDate) -> Goal {
+        let mut goal = Goal::new("Bike", target_amount, target_date, Ulid::new());
+        goal.saved = saved;
+        goal.created_at = created.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        goal
+    }
+
+    #[test]
+    fn months_between_counts_only_full_months() {
+        assert_eq!(months_between(date(2024, 1, 15), date(2024, 4, 15)), 3);
+        assert_eq!(months_between(date(2024, 1, 15), date(2024, 4, 14)), 2);
+        assert_eq!(months_between(date(2024, 11, 30), date(2025, 2, 28)), 2);
+        assert_eq!(months_between(date(2024, 4, 15), date(2024, 1, 15)), -3);
+    }
+
+    #[test]
+    fn required_monthly_spreads_the_remaining_amount() {
+        let today = date(2024, 1, 1);
+        let bike = goal(1000.0, date(2024, 5, 1), 200.0, today);
+        assert_eq!(bike.required_monthly(today), 200.0);
+    }
+
+    #[test]
+    fn a_past_or_near_target_asks_for_everything_left_at_once() {
+        let today = date(2024, 6, 1);
+        let late = goal(1000.0, date(2024, 1, 1), 400.0, date(2023, 1, 1));
+        assert_eq!(late.required_monthly(today), 600.0);
+        let soon = goal(1000.0, date(2024, 6, 20), 400.0, date(2023, 1, 1));
+        assert_eq!(soon.required_monthly(today), 600.0);
+    }
+
+    #[test]
+    fn a_reached_goal_needs_nothing_more() {
+        let today = date(2024, 6, 1);
+        let done = goal(1000.0, date(2024, 12, 1), 1200.0, date(2024, 1, 1));
+        assert!(done.is_reached());
+        assert_eq!(done.remaining(), 0.0);
+        assert_eq!(done.progress(), 100.0);
+        assert_eq!(done.required_monthly(today), 0.0);
+        assert_eq!(done.projected_completion(today), Some(today));
+    }
+
+    #[test]
+    fn projected_completion_keeps_the_pace_since_creation() {
+        let today = date(2024, 1, 11);
+        // 100 a day over ten days, 500 left
+        let bike = goal(1500.0, date(2024, 12, 1), 1000.0, date(2024, 1, 1));
+        assert_eq!(bike.projected_completion(today), Some(date(2024, 1, 16)));
+    }
+
+    #[test]
+    fn nothing_saved_has_no_projection() {
+        let today = date(2024, 1, 1);
+        let bike = goal(1500.0, date(2024, 12, 1), 0.0, today);
+        assert_eq!(bike.projected_completion(today), None);
+        assert_eq!(bike.progress(), 0.0);
+    }
+}
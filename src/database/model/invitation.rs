@@ -0,0 +1,111 @@
+use chrono::serde::ts_milliseconds;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::prelude::FromRow;
+use sqlx::Row;
+use ulid::{serde::ulid_as_u128, Ulid};
+
+use crate::{database::model::book::Role, utils};
+
+// how long an invitation can be accepted after it is sent
+pub const INVITATION_DAYS: i64 = 7;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum InvitationStatus {
+    Pending,
+    Accepted,
+    Declined,
+}
+
+impl InvitationStatus {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "accepted" => InvitationStatus::Accepted,
+            "declined" => InvitationStatus::Declined,
+            _ => InvitationStatus::Pending,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InvitationStatus::Pending => "pending",
+            InvitationStatus::Accepted => "accepted",
+            InvitationStatus::Declined => "declined",
+        }
+    }
+}
+
+// an invitation goes to an email rather than an account, so people who have
+// not signed up yet can accept it once they do
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Invitation {
+    #[serde(with = "ulid_as_u128")]
+    pub id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub book_id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub invited_by: Ulid,
+    pub token: String,
+    pub email: String,
+    pub role: Role,
+    pub status: InvitationStatus,
+    pub expires_at: DateTime<Utc>,
+    // filled from the books table when listing invitations
+    pub book_name: String,
+
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Invitation {
+    pub fn new(book_id: Ulid, invited_by: Ulid, email: &str, role: Role) -> Self {
+        let id = ulid::Ulid::new();
+        let created_at = chrono::offset::Utc::now();
+        Self {
+            id,
+            book_id,
+            invited_by,
+            token: utils::generate_random_string(32),
+            email: email.trim().to_lowercase(),
+            role,
+            status: InvitationStatus::Pending,
+            expires_at: created_at + Duration::days(INVITATION_DAYS),
+            book_name: String::new(),
+            created_at,
+            updated_at: None,
+            deleted_at: None,
+        }
+    }
+}
+
+impl FromRow<'_, PgRow> for Invitation {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let id: [u8; 16] = row.get("id");
+        let book_id: [u8; 16] = row.get("book_id");
+        let invited_by: [u8; 16] = row.get("invited_by");
+        let role: String = row.get("role");
+        let status: String = row.get("status");
+        let created_at: DateTime<Utc> = row.get("created_at");
+        let updated_at: Option<DateTime<Utc>> = row.try_get("updated_at").unwrap_or_default();
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
+
+        let res: Invitation = Self {
+            id: Ulid::from_bytes(id),
+            book_id: Ulid::from_bytes(book_id),
+            invited_by: Ulid::from_bytes(invited_by),
+            token: row.get("token"),
+            email: row.get("email"),
+            role: Role::parse(&role).unwrap_or(Role::Viewer),
+            status: InvitationStatus::parse(&status),
+            expires_at: row.get("expires_at"),
+            book_name: row.try_get("book_name").unwrap_or_default(),
+            created_at,
+            updated_at,
+            deleted_at,
+        };
+        Ok(res)
+    }
+}
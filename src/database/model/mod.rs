@@ -1,5 +1,15 @@
 pub mod account;
 pub mod book;
 pub mod category;
+pub mod debt;
+pub mod forecast;
+pub mod goal;
+pub mod invitation;
+pub mod payee;
+pub mod preset;
 pub mod record;
+pub mod recurring;
+pub mod report;
+pub mod rule;
 pub mod session;
+pub mod split;
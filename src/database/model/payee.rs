@@ -0,0 +1,80 @@
+use chrono::serde::ts_milliseconds;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::prelude::FromRow;
+use sqlx::Row;
+use ulid::{serde::ulid_as_u128, Ulid};
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Payee {
+    #[serde(with = "ulid_as_u128")]
+    pub id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub book_id: Ulid,
+    pub name: String,
+    // pre-selected on the record form when the payee is chosen
+    pub default_category_id: Option<Ulid>,
+
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Payee {
+    pub fn new(name: &str, book_id: Ulid) -> Self {
+        let id = ulid::Ulid::new();
+        let created_at = chrono::offset::Utc::now();
+        Self {
+            id,
+            book_id,
+            name: name.trim().to_string(),
+            default_category_id: None,
+            created_at,
+            updated_at: None,
+            deleted_at: None,
+        }
+    }
+}
+
+impl FromRow<'_, PgRow> for Payee {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let id: [u8; 16] = row.get("id");
+        let book_id: [u8; 16] = row.get("book_id");
+        let default_category_id: Option<[u8; 16]> =
+            row.try_get("default_category_id").unwrap_or_default();
+        let created_at: DateTime<Utc> = row.get("created_at");
+        let updated_at: Option<DateTime<Utc>> = row.try_get("updated_at").unwrap_or_default();
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
+
+        let res: Payee = Self {
+            id: Ulid::from_bytes(id),
+            book_id: Ulid::from_bytes(book_id),
+            name: row.get("name"),
+            default_category_id: default_category_id.map(Ulid::from_bytes),
+            created_at,
+            updated_at,
+            deleted_at,
+        };
+        Ok(res)
+    }
+}
+
+// spending on a payee within a date range
+#[derive(Clone, Debug)]
+pub struct PayeeTotal {
+    pub payee: Payee,
+    pub total: f32,
+    pub record_count: i64,
+}
+
+impl FromRow<'_, PgRow> for PayeeTotal {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            payee: Payee::from_row(row)?,
+            total: row.get("total"),
+            record_count: row.get("record_count"),
+        })
+    }
+}
@@ -0,0 +1,131 @@
+use serde::Deserialize;
+use std::sync::OnceLock;
+use ulid::Ulid;
+
+use super::category::{Category, DEFAULT_COLOR};
+
+// starter category sets, written as yaml files in `presets/` and compiled into
+// the binary, so changing one needs a rebuild
+static PRESET_FILES: [(&str, &str); 3] = [
+    (
+        "en-personal",
+        include_str!("../../../presets/en-personal.yml"),
+    ),
+    (
+        "id-household",
+        include_str!("../../../presets/id-household.yml"),
+    ),
+    (
+        "small-business",
+        include_str!("../../../presets/small-business.yml"),
+    ),
+];
+
+pub static DEFAULT_PRESET: &str = "en-personal";
+
+static PRESETS: OnceLock<Vec<Preset>> = OnceLock::new();
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PresetCategory {
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub is_expense: bool,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    // sub-categories share the type of their parent
+    #[serde(default)]
+    pub children: Vec<PresetCategory>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Preset {
+    #[serde(skip)]
+    pub id: String,
+    pub name: String,
+    pub categories: Vec<PresetCategory>,
+}
+
+impl Preset {
+    // parsed on first use and kept for the life of the process
+    pub fn all() -> &'static [Self] {
+        PRESETS.get_or_init(|| {
+            PRESET_FILES
+                .iter()
+                .map(|(id, content)| {
+                    let mut preset: Preset =
+                        serde_yaml::from_str(content).expect("Should parse category preset");
+                    preset.id = id.to_string();
+                    preset
+                })
+                .collect()
+        })
+    }
+
+    pub fn find(id: &str) -> Option<&'static Self> {
+        Self::all().iter().find(|p| p.id == id)
+    }
+
+    // the categories of the preset for a book; a parent is always listed
+    // before its sub-categories so they can be inserted in order
+    pub fn categories(&self, book_id: Ulid) -> Vec<Category> {
+        let mut datas: Vec<Category> = Vec::new();
+        for c in &self.categories {
+            push_category(&mut datas, c, c.is_expense, book_id, None);
+        }
+        datas
+    }
+}
+
+fn push_category(
+    datas: &mut Vec<Category>,
+    preset: &PresetCategory,
+    is_expense: bool,
+    book_id: Ulid,
+    parent_id: Option<Ulid>,
+) {
+    let mut category = Category::new(
+        &preset.name,
+        &preset.description,
+        is_expense,
+        book_id,
+        parent_id,
+    );
+    category.color = preset.color.clone().unwrap_or(DEFAULT_COLOR.to_string());
+    category.icon = preset.icon.clone().unwrap_or_default();
+    let id = category.id;
+    datas.push(category);
+    for child in &preset.children {
+        push_category(datas, child, is_expense, book_id, Some(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::model::category::ICONS;
+
+    fn icons(categories: &[PresetCategory], found: &mut Vec<String>) {
+        for c in categories {
+            found.extend(c.icon.clone());
+            icons(&c.children, found);
+        }
+    }
+
+    #[test]
+    fn every_preset_parses_with_known_icons() {
+        let presets = Preset::all();
+        assert_eq!(presets.len(), PRESET_FILES.len());
+        assert!(Preset::find(DEFAULT_PRESET).is_some());
+        for preset in presets {
+            assert!(!preset.categories.is_empty(), "{} is empty", preset.id);
+            let mut found = Vec::new();
+            icons(&preset.categories, &mut found);
+            for icon in found {
+                assert!(ICONS.contains(&icon.as_str()), "{} in {}", icon, preset.id);
+            }
+        }
+    }
+}
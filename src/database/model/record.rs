@@ -15,8 +15,21 @@ pub struct Record {
     #[serde(with = "ulid_as_u128")]
     pub category_id: Ulid,
     pub category_name: String,
+    pub category_color: String,
+    pub category_icon: String,
+    pub payee_id: Option<Ulid>,
+    pub payee_name: String,
+    // only known when the query spans several books
+    #[serde(default)]
+    pub book_name: String,
     pub notes: String,
     pub amount: f32,
+    // the account that wrote the record, unknown for records older than roles
+    pub created_by: Option<Ulid>,
+    // the rules of the book picked the category, see `querier::rule::apply`
+    pub category_from_rule: bool,
+    // moves on with every change, see `querier::record::edit`
+    pub version: i32,
 
     #[serde(with = "ts_milliseconds")]
     pub created_at: DateTime<Utc>,
@@ -24,6 +37,17 @@ pub struct Record {
     pub deleted_at: Option<DateTime<Utc>>,
 }
 
+#[derive(Serialize)]
+pub struct RecordJson {
+    pub id: String,
+    pub category_id: String,
+    pub category_name: String,
+    pub payee_name: String,
+    pub notes: String,
+    pub amount: f32,
+    pub created_at: DateTime<Utc>,
+}
+
 impl Record {
     pub fn new(notes: &str, amount: f32, book_id: Ulid, category_id: Ulid) -> Self {
         let id = ulid::Ulid::new();
@@ -32,33 +56,63 @@ impl Record {
             id,
             created_at,
             category_name: "".to_string(),
+            category_color: "".to_string(),
+            category_icon: "".to_string(),
+            payee_id: None,
+            payee_name: "".to_string(),
+            book_name: "".to_string(),
             updated_at: None,
             deleted_at: None,
             book_id,
             category_id,
             notes: notes.to_string(),
             amount,
+            created_by: None,
+            category_from_rule: false,
+            version: 1,
+        }
+    }
+
+    pub fn to_json(&self) -> RecordJson {
+        let record = self.clone();
+        RecordJson {
+            id: record.id.to_string(),
+            category_id: record.category_id.to_string(),
+            category_name: record.category_name,
+            payee_name: record.payee_name,
+            notes: record.notes,
+            amount: record.amount,
+            created_at: record.created_at,
         }
     }
 }
 
+// lowercase words of the notes worth matching against other records, short
+// words like "di" or "to" are skipped
+pub fn note_keywords(notes: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for word in notes.split(|c: char| !c.is_alphanumeric()) {
+        let word = word.to_lowercase();
+        if word.chars().count() >= 3 && !words.contains(&word) {
+            words.push(word);
+        }
+    }
+    words.truncate(5);
+    words
+}
+
 impl FromRow<'_, PgRow> for Record {
     fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
         let id: [u8; 16] = row.get("id");
         let category_id: [u8; 16] = row.get("category_id");
         let category_name: String = row.get("category_name");
         let book_id: [u8; 16] = row.get("book_id");
+        let payee_id: Option<[u8; 16]> = row.try_get("payee_id").unwrap_or_default();
         let notes: String = row.get("notes");
         let amount: f32 = row.get("amount");
         let created_at: DateTime<Utc> = row.get("created_at");
-        let updated_at: Option<DateTime<Utc>> = match row.try_get("updated_at") {
-            Ok(v) => v,
-            Err(_) => None,
-        };
-        let deleted_at: Option<DateTime<Utc>> = match row.try_get("deleted_at") {
-            Ok(v) => v,
-            Err(_) => None,
-        };
+        let updated_at: Option<DateTime<Utc>> = row.try_get("updated_at").unwrap_or_default();
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
 
         let res: Record = Self {
             id: Ulid::from_bytes(id),
@@ -70,6 +124,17 @@ impl FromRow<'_, PgRow> for Record {
             notes,
             amount,
             category_name,
+            category_color: row.try_get("category_color").unwrap_or_default(),
+            category_icon: row.try_get("category_icon").unwrap_or_default(),
+            payee_id: payee_id.map(Ulid::from_bytes),
+            payee_name: row.try_get("payee_name").unwrap_or_default(),
+            book_name: row.try_get("book_name").unwrap_or_default(),
+            created_by: row
+                .try_get::<Option<[u8; 16]>, _>("created_by")
+                .unwrap_or_default()
+                .map(Ulid::from_bytes),
+            category_from_rule: row.try_get("category_from_rule").unwrap_or_default(),
+            version: row.try_get("version").unwrap_or_default(),
         };
         Ok(res)
     }
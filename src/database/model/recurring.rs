@@ -0,0 +1,95 @@
+use chrono::serde::ts_milliseconds;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::prelude::FromRow;
+use sqlx::Row;
+use ulid::{serde::ulid_as_u128, Ulid};
+
+use super::report::{add_months, first_day_of_month};
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Recurring {
+    #[serde(with = "ulid_as_u128")]
+    pub id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub book_id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub category_id: Ulid,
+    pub category_name: String,
+    pub is_expense: bool,
+    pub notes: String,
+    pub amount: f32,
+    pub day_of_month: i32,
+
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Recurring {
+    pub fn new(
+        notes: &str,
+        amount: f32,
+        day_of_month: i32,
+        book_id: Ulid,
+        category_id: Ulid,
+    ) -> Self {
+        let id = ulid::Ulid::new();
+        let created_at = chrono::offset::Utc::now();
+        Self {
+            id,
+            created_at,
+            category_name: "".to_string(),
+            is_expense: true,
+            updated_at: None,
+            deleted_at: None,
+            book_id,
+            category_id,
+            notes: notes.to_string(),
+            amount,
+            day_of_month: day_of_month.clamp(1, 31),
+        }
+    }
+
+    // the date this item falls on in the budgeting month starting at `start`,
+    // which runs into the next calendar month when months don't start on the
+    // 1st; clamped to the last day of shorter months
+    pub fn due_in(&self, start: NaiveDate) -> NaiveDate {
+        let day = (self.day_of_month as u32).clamp(1, 31);
+        let month = if day >= start.day() {
+            first_day_of_month(start.year(), start.month())
+        } else {
+            add_months(start, 1)
+        };
+        let last_day = add_months(month, 1).pred_opt().unwrap().day();
+        NaiveDate::from_ymd_opt(month.year(), month.month(), day.min(last_day)).unwrap()
+    }
+}
+
+impl FromRow<'_, PgRow> for Recurring {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let id: [u8; 16] = row.get("id");
+        let category_id: [u8; 16] = row.get("category_id");
+        let book_id: [u8; 16] = row.get("book_id");
+        let created_at: DateTime<Utc> = row.get("created_at");
+        let updated_at: Option<DateTime<Utc>> = row.try_get("updated_at").unwrap_or_default();
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
+
+        let res: Recurring = Self {
+            id: Ulid::from_bytes(id),
+            created_at,
+            updated_at,
+            deleted_at,
+            book_id: Ulid::from_bytes(book_id),
+            category_id: Ulid::from_bytes(category_id),
+            category_name: row.get("category_name"),
+            is_expense: row.get("is_expense"),
+            notes: row.get("notes"),
+            amount: row.get("amount"),
+            day_of_month: row.get("day_of_month"),
+        };
+        Ok(res)
+    }
+}
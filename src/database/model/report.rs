@@ -0,0 +1,651 @@
+use super::{
+    book::BookSettings,
+    record::{Record, RecordJson},
+};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::prelude::FromRow;
+use sqlx::Row;
+use ulid::Ulid;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Period {
+    Month,
+    Quarter,
+    Year,
+    Custom,
+}
+
+impl Period {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "quarter" => Period::Quarter,
+            "year" => Period::Year,
+            "custom" => Period::Custom,
+            _ => Period::Month,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Period::Month => "month",
+            Period::Quarter => "quarter",
+            Period::Year => "year",
+            Period::Custom => "custom",
+        }
+    }
+}
+
+// date range of a report in the book's local days, `from` is inclusive and
+// `to` is exclusive; months, quarters and years start on the book's month
+// start day
+#[derive(Clone, Copy, Debug)]
+pub struct DateRange {
+    pub period: Period,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub settings: BookSettings,
+}
+
+impl DateRange {
+    pub fn new(period: Period, anchor: NaiveDate, settings: BookSettings) -> Self {
+        let day = settings.month_start_day;
+        let month = budget_month_start(anchor, day);
+        let (from, to) = match period {
+            Period::Month => (month, shift_months(month, 1)),
+            Period::Quarter => {
+                let from = NaiveDate::from_ymd_opt(month.year(), (month.month0() / 3) * 3 + 1, day)
+                    .unwrap();
+                (from, shift_months(from, 3))
+            }
+            Period::Year => {
+                let from = NaiveDate::from_ymd_opt(month.year(), 1, day).unwrap();
+                (from, shift_months(from, 12))
+            }
+            Period::Custom => (anchor, anchor + Duration::days(1)),
+        };
+        Self {
+            period,
+            from,
+            to,
+            settings,
+        }
+    }
+
+    // builds a custom range from two inclusive dates
+    pub fn custom(from: NaiveDate, until: NaiveDate, settings: BookSettings) -> Self {
+        let (from, until) = if from > until {
+            (until, from)
+        } else {
+            (from, until)
+        };
+        Self {
+            period: Period::Custom,
+            from,
+            to: until + Duration::days(1),
+            settings,
+        }
+    }
+
+    pub fn start(&self) -> DateTime<Utc> {
+        self.settings.midnight(self.from)
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        self.settings.midnight(self.to)
+    }
+
+    pub fn last_day(&self) -> NaiveDate {
+        self.to - Duration::days(1)
+    }
+
+    pub fn previous(&self) -> Self {
+        match self.period {
+            Period::Month => Self::new(self.period, shift_months(self.from, -1), self.settings),
+            Period::Quarter => Self::new(self.period, shift_months(self.from, -3), self.settings),
+            Period::Year => Self::new(self.period, shift_months(self.from, -12), self.settings),
+            Period::Custom => {
+                let days = (self.to - self.from).num_days();
+                Self::custom(
+                    self.from - Duration::days(days),
+                    self.from - Duration::days(1),
+                    self.settings,
+                )
+            }
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self.period {
+            Period::Custom => {
+                let days = (self.to - self.from).num_days();
+                Self::custom(self.to, self.to + Duration::days(days - 1), self.settings)
+            }
+            _ => Self::new(self.period, self.to, self.settings),
+        }
+    }
+}
+
+pub fn first_day_of_month(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+}
+
+pub fn add_months(date: NaiveDate, months: i32) -> NaiveDate {
+    let total = date.year() * 12 + date.month0() as i32 + months;
+    first_day_of_month(total.div_euclid(12), total.rem_euclid(12) as u32 + 1)
+}
+
+// like `add_months` but keeps the day, which every month must have
+pub fn shift_months(date: NaiveDate, months: i32) -> NaiveDate {
+    let month = add_months(date, months);
+    NaiveDate::from_ymd_opt(month.year(), month.month(), date.day()).unwrap()
+}
+
+// first day of the budgeting month holding `date` when months start on `day`
+pub fn budget_month_start(date: NaiveDate, day: u32) -> NaiveDate {
+    let month = NaiveDate::from_ymd_opt(date.year(), date.month(), day).unwrap();
+    if date < month {
+        shift_months(month, -1)
+    } else {
+        month
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CategoryTotal {
+    pub category_id: Ulid,
+    pub name: String,
+    pub is_expense: bool,
+    pub color: String,
+    pub icon: String,
+    pub total: f32,
+    pub record_count: i64,
+}
+
+impl FromRow<'_, PgRow> for CategoryTotal {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let category_id: [u8; 16] = row.get("category_id");
+        let res: CategoryTotal = Self {
+            category_id: Ulid::from_bytes(category_id),
+            name: row.get("name"),
+            is_expense: row.get("is_expense"),
+            color: row.try_get("color").unwrap_or_default(),
+            icon: row.try_get("icon").unwrap_or_default(),
+            total: row.get("total"),
+            record_count: row.get("record_count"),
+        };
+        Ok(res)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CategoryShareJson {
+    pub category_id: String,
+    pub name: String,
+    pub total: f32,
+    pub percentage: f32,
+    pub record_count: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReportJson {
+    pub period: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total_income: f32,
+    pub total_expense: f32,
+    pub net: f32,
+    pub incomes: Vec<CategoryShareJson>,
+    pub expenses: Vec<CategoryShareJson>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub range: DateRange,
+    pub incomes: Vec<CategoryTotal>,
+    pub expenses: Vec<CategoryTotal>,
+    pub total_income: f32,
+    pub total_expense: f32,
+}
+
+impl Report {
+    pub fn new(range: DateRange, totals: Vec<CategoryTotal>) -> Self {
+        let (expenses, incomes): (Vec<CategoryTotal>, Vec<CategoryTotal>) =
+            totals.into_iter().partition(|t| t.is_expense);
+        let total_income = incomes.iter().map(|t| t.total).sum();
+        let total_expense = expenses.iter().map(|t| t.total).sum();
+        Self {
+            range,
+            incomes,
+            expenses,
+            total_income,
+            total_expense,
+        }
+    }
+
+    pub fn net(&self) -> f32 {
+        self.total_income - self.total_expense
+    }
+
+    // share of a category against the total of its own side (income or expense)
+    pub fn percentage(&self, total: &CategoryTotal) -> f32 {
+        let side = if total.is_expense {
+            self.total_expense
+        } else {
+            self.total_income
+        };
+        if side == 0.0 {
+            0.0
+        } else {
+            total.total / side * 100.0
+        }
+    }
+
+    pub fn to_json(&self) -> ReportJson {
+        let share = |t: &CategoryTotal| CategoryShareJson {
+            category_id: t.category_id.to_string(),
+            name: t.name.clone(),
+            total: t.total,
+            percentage: self.percentage(t),
+            record_count: t.record_count,
+        };
+        ReportJson {
+            period: self.range.period.as_str().to_string(),
+            from: self.range.from,
+            to: self.range.last_day(),
+            total_income: self.total_income,
+            total_expense: self.total_expense,
+            net: self.net(),
+            incomes: self.incomes.iter().map(share).collect(),
+            expenses: self.expenses.iter().map(share).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bucket {
+    Month,
+    Year,
+}
+
+impl Bucket {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "year" => Bucket::Year,
+            _ => Bucket::Month,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Bucket::Month => "month",
+            Bucket::Year => "year",
+        }
+    }
+
+    // buckets start on the month start `day`, years in January
+    pub fn start_of(&self, date: NaiveDate, day: u32) -> NaiveDate {
+        let month = budget_month_start(date, day);
+        match self {
+            Bucket::Month => month,
+            Bucket::Year => NaiveDate::from_ymd_opt(month.year(), 1, day).unwrap(),
+        }
+    }
+
+    pub fn shift(&self, date: NaiveDate, count: i32) -> NaiveDate {
+        match self {
+            Bucket::Month => shift_months(date, count),
+            Bucket::Year => shift_months(date, count * 12),
+        }
+    }
+
+    // the last `count` bucket starts, oldest first, ending with the bucket containing `date`
+    pub fn last(&self, date: NaiveDate, count: u32, day: u32) -> Vec<NaiveDate> {
+        let current = self.start_of(date, day);
+        (0..count as i32)
+            .rev()
+            .map(|i| self.shift(current, -i))
+            .collect()
+    }
+
+    // time range covered by a list of bucket starts
+    pub fn span(
+        &self,
+        buckets: &[NaiveDate],
+        settings: &BookSettings,
+    ) -> (DateTime<Utc>, DateTime<Utc>) {
+        let first = buckets.first().copied().unwrap_or_default();
+        let end = self.shift(buckets.last().copied().unwrap_or_default(), 1);
+        (settings.midnight(first), settings.midnight(end))
+    }
+
+    pub fn label(&self, date: NaiveDate) -> String {
+        match self {
+            Bucket::Month => date.format("%b %Y").to_string(),
+            Bucket::Year => date.format("%Y").to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CategoryBucketTotal {
+    pub category_id: Ulid,
+    pub name: String,
+    pub is_expense: bool,
+    pub bucket: NaiveDate,
+    pub total: f32,
+}
+
+impl FromRow<'_, PgRow> for CategoryBucketTotal {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let category_id: [u8; 16] = row.get("category_id");
+        let bucket: NaiveDateTime = row.get("bucket");
+        let res: CategoryBucketTotal = Self {
+            category_id: Ulid::from_bytes(category_id),
+            name: row.get("name"),
+            is_expense: row.get("is_expense"),
+            bucket: bucket.date(),
+            total: row.get("total"),
+        };
+        Ok(res)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CategoryTrend {
+    pub category_id: Ulid,
+    pub name: String,
+    pub is_expense: bool,
+    pub totals: Vec<f32>,
+}
+
+impl CategoryTrend {
+    fn last_two(&self) -> (f32, f32) {
+        match self.totals.as_slice() {
+            [.., previous, current] => (*previous, *current),
+            [current] => (0.0, *current),
+            [] => (0.0, 0.0),
+        }
+    }
+
+    // absolute change of the latest bucket against the one before it
+    pub fn change(&self) -> f32 {
+        let (previous, current) = self.last_two();
+        current - previous
+    }
+
+    // relative change in percent, none when the previous bucket is empty
+    pub fn change_percentage(&self) -> Option<f32> {
+        let (previous, _) = self.last_two();
+        if previous == 0.0 {
+            None
+        } else {
+            Some(self.change() / previous * 100.0)
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CategoryTrendJson {
+    pub category_id: String,
+    pub name: String,
+    pub is_expense: bool,
+    pub totals: Vec<f32>,
+    pub change: f32,
+    pub change_percentage: Option<f32>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TrendJson {
+    pub bucket: String,
+    pub buckets: Vec<NaiveDate>,
+    pub categories: Vec<CategoryTrendJson>,
+    pub largest_increases: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Trend {
+    pub bucket: Bucket,
+    pub buckets: Vec<NaiveDate>,
+    pub categories: Vec<CategoryTrend>,
+}
+
+impl Trend {
+    pub fn new(bucket: Bucket, buckets: Vec<NaiveDate>, rows: Vec<CategoryBucketTotal>) -> Self {
+        let mut categories: Vec<CategoryTrend> = Vec::new();
+        for row in rows {
+            let index = match buckets.iter().position(|b| *b == row.bucket) {
+                Some(i) => i,
+                None => continue,
+            };
+            let trend = match categories
+                .iter_mut()
+                .position(|c| c.category_id == row.category_id)
+            {
+                Some(i) => &mut categories[i],
+                None => {
+                    categories.push(CategoryTrend {
+                        category_id: row.category_id,
+                        name: row.name,
+                        is_expense: row.is_expense,
+                        totals: vec![0.0; buckets.len()],
+                    });
+                    categories.last_mut().unwrap()
+                }
+            };
+            trend.totals[index] += row.total;
+        }
+        categories.sort_by(|a, b| b.is_expense.cmp(&a.is_expense).then(a.name.cmp(&b.name)));
+        Self {
+            bucket,
+            buckets,
+            categories,
+        }
+    }
+
+    // expense categories with the biggest growth in the latest bucket
+    pub fn largest_increases(&self, count: usize) -> Vec<&CategoryTrend> {
+        let mut increases: Vec<&CategoryTrend> = self
+            .categories
+            .iter()
+            .filter(|c| c.is_expense && c.change() > 0.0)
+            .collect();
+        increases.sort_by(|a, b| b.change().total_cmp(&a.change()));
+        increases.truncate(count);
+        increases
+    }
+
+    pub fn to_json(&self) -> TrendJson {
+        TrendJson {
+            bucket: self.bucket.as_str().to_string(),
+            buckets: self.buckets.clone(),
+            categories: self
+                .categories
+                .iter()
+                .map(|c| CategoryTrendJson {
+                    category_id: c.category_id.to_string(),
+                    name: c.name.clone(),
+                    is_expense: c.is_expense,
+                    totals: c.totals.clone(),
+                    change: c.change(),
+                    change_percentage: c.change_percentage(),
+                })
+                .collect(),
+            largest_increases: self
+                .largest_increases(3)
+                .iter()
+                .map(|c| c.category_id.to_string())
+                .collect(),
+        }
+    }
+}
+
+// one book's totals on the account overview, over the range of the overview;
+// its settings only format the dates of its records
+#[derive(Clone, Debug)]
+pub struct BookTotal {
+    pub book_id: Ulid,
+    pub name: String,
+    pub settings: BookSettings,
+    pub total_income: f32,
+    pub total_expense: f32,
+}
+
+impl BookTotal {
+    pub fn net(&self) -> f32 {
+        self.total_income - self.total_expense
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BookTotalJson {
+    pub book_id: String,
+    pub name: String,
+    pub total_income: f32,
+    pub total_expense: f32,
+    pub net: f32,
+}
+
+#[derive(Serialize)]
+pub struct OverviewRecordJson {
+    #[serde(flatten)]
+    pub record: RecordJson,
+    pub book_id: String,
+    pub book_name: String,
+}
+
+#[derive(Serialize)]
+pub struct OverviewJson {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub books: Vec<BookTotalJson>,
+    pub total_income: f32,
+    pub total_expense: f32,
+    pub net: f32,
+    pub records: Vec<OverviewRecordJson>,
+}
+
+// every book of an account side by side over the same range. Books have no
+// currency of their own, all amounts are rupiah, so the totals add up without
+// any conversion
+#[derive(Clone, Debug)]
+pub struct Overview {
+    pub range: DateRange,
+    pub books: Vec<BookTotal>,
+    pub records: Vec<Record>,
+}
+
+impl Overview {
+    pub fn total_income(&self) -> f32 {
+        self.books.iter().map(|b| b.total_income).sum()
+    }
+
+    pub fn total_expense(&self) -> f32 {
+        self.books.iter().map(|b| b.total_expense).sum()
+    }
+
+    pub fn net(&self) -> f32 {
+        self.total_income() - self.total_expense()
+    }
+
+    pub fn to_json(&self) -> OverviewJson {
+        OverviewJson {
+            from: self.range.from,
+            to: self.range.last_day(),
+            books: self
+                .books
+                .iter()
+                .map(|b| BookTotalJson {
+                    book_id: b.book_id.to_string(),
+                    name: b.name.clone(),
+                    total_income: b.total_income,
+                    total_expense: b.total_expense,
+                    net: b.net(),
+                })
+                .collect(),
+            total_income: self.total_income(),
+            total_expense: self.total_expense(),
+            net: self.net(),
+            records: self
+                .records
+                .iter()
+                .map(|r| OverviewRecordJson {
+                    record: r.to_json(),
+                    book_id: r.book_id.to_string(),
+                    book_name: r.book_name.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn month_range_covers_the_calendar_month() {
+        let range = DateRange::new(Period::Month, date(2024, 2, 14), BookSettings::default());
+        assert_eq!(range.from, date(2024, 2, 1));
+        assert_eq!(range.to, date(2024, 3, 1));
+        assert_eq!(range.last_day(), date(2024, 2, 29));
+    }
+
+    #[test]
+    fn quarter_and_year_ranges_start_on_their_first_month() {
+        let settings = BookSettings::default();
+        let quarter = DateRange::new(Period::Quarter, date(2024, 8, 20), settings);
+        assert_eq!(quarter.from, date(2024, 7, 1));
+        assert_eq!(quarter.to, date(2024, 10, 1));
+        let year = DateRange::new(Period::Year, date(2024, 8, 20), settings);
+        assert_eq!(year.from, date(2024, 1, 1));
+        assert_eq!(year.to, date(2025, 1, 1));
+    }
+
+    #[test]
+    fn previous_and_next_cross_year_boundaries() {
+        let range = DateRange::new(Period::Month, date(2024, 1, 10), BookSettings::default());
+        let previous = range.previous();
+        assert_eq!(previous.from, date(2023, 12, 1));
+        assert_eq!(previous.to, date(2024, 1, 1));
+        let next = previous.next();
+        assert_eq!(next.from, range.from);
+        assert_eq!(next.to, range.to);
+    }
+
+    #[test]
+    fn custom_range_includes_both_days_and_moves_by_its_length() {
+        let settings = BookSettings::default();
+        let range = DateRange::custom(date(2024, 3, 10), date(2024, 3, 1), settings);
+        assert_eq!(range.from, date(2024, 3, 1));
+        assert_eq!(range.last_day(), date(2024, 3, 10));
+        let previous = range.previous();
+        assert_eq!(previous.from, date(2024, 2, 20));
+        assert_eq!(previous.last_day(), date(2024, 2, 29));
+        let next = range.next();
+        assert_eq!(next.from, date(2024, 3, 11));
+        assert_eq!(next.last_day(), date(2024, 3, 20));
+    }
+
+    #[test]
+    fn months_start_on_the_book_month_start_day() {
+        let settings = BookSettings::new("UTC", 25);
+        let range = DateRange::new(Period::Month, date(2024, 1, 3), settings);
+        assert_eq!(range.from, date(2023, 12, 25));
+        assert_eq!(range.last_day(), date(2024, 1, 24));
+        let range = DateRange::new(Period::Month, date(2024, 1, 25), settings);
+        assert_eq!(range.from, date(2024, 1, 25));
+        assert_eq!(range.to, date(2024, 2, 25));
+    }
+
+    #[test]
+    fn add_months_wraps_years_both_ways() {
+        assert_eq!(add_months(date(2024, 11, 15), 3), date(2025, 2, 1));
+        assert_eq!(add_months(date(2024, 2, 15), -3), date(2023, 11, 1));
+    }
+}
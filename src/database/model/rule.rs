@@ -0,0 +1,260 @@
+use chrono::serde::ts_milliseconds;
+use chrono::{DateTime, Utc};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::prelude::FromRow;
+use sqlx::Row;
+use ulid::{serde::ulid_as_u128, Ulid};
+
+static PATTERN_MISSING: &str = "a contains or regex rule needs a pattern";
+static PATTERN_INVALID: &str = "pattern is not a valid regular expression";
+static RANGE_MISSING: &str = "an amount rule needs a minimum or a maximum amount";
+static RANGE_INVALID: &str = "minimum amount cannot be greater than maximum amount";
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum RuleKind {
+    Contains,
+    Regex,
+    Amount,
+}
+
+impl RuleKind {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "regex" => RuleKind::Regex,
+            "amount" => RuleKind::Amount,
+            _ => RuleKind::Contains,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleKind::Contains => "contains",
+            RuleKind::Regex => "regex",
+            RuleKind::Amount => "amount",
+        }
+    }
+}
+
+// picks a category for records whose notes (and amount) match; an amount
+// range narrows contains and regex rules too
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Rule {
+    #[serde(with = "ulid_as_u128")]
+    pub id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub book_id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub category_id: Ulid,
+    pub category_name: String,
+    pub kind: RuleKind,
+    pub pattern: String,
+    pub min_amount: Option<f32>,
+    pub max_amount: Option<f32>,
+
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Rule {
+    pub fn new(
+        kind: RuleKind,
+        pattern: &str,
+        min_amount: Option<f32>,
+        max_amount: Option<f32>,
+        book_id: Ulid,
+        category_id: Ulid,
+    ) -> Result<Self, &'static str> {
+        let pattern = pattern.trim();
+        match kind {
+            RuleKind::Contains | RuleKind::Regex if pattern.is_empty() => {
+                return Err(PATTERN_MISSING)
+            }
+            RuleKind::Regex if RegexBuilder::new(pattern).build().is_err() => {
+                return Err(PATTERN_INVALID)
+            }
+            RuleKind::Amount if min_amount.is_none() && max_amount.is_none() => {
+                return Err(RANGE_MISSING)
+            }
+            _ => {}
+        }
+        if let (Some(min), Some(max)) = (min_amount, max_amount) {
+            if min > max {
+                return Err(RANGE_INVALID);
+            }
+        }
+        Ok(Self {
+            id: ulid::Ulid::new(),
+            book_id,
+            category_id,
+            category_name: "".to_string(),
+            kind,
+            pattern: match kind {
+                RuleKind::Amount => "".to_string(),
+                _ => pattern.to_string(),
+            },
+            min_amount,
+            max_amount,
+            created_at: chrono::offset::Utc::now(),
+            updated_at: None,
+            deleted_at: None,
+        })
+    }
+
+    // compiles the pattern once, to match many records with the rule
+    pub fn matcher(&self) -> Matcher<'_> {
+        let regex = match self.kind {
+            RuleKind::Regex => RegexBuilder::new(&self.pattern)
+                .case_insensitive(true)
+                .build()
+                .ok(),
+            _ => None,
+        };
+        Matcher {
+            rule: self,
+            pattern: self.pattern.to_lowercase(),
+            regex,
+        }
+    }
+
+    pub fn matches(&self, notes: &str, amount: f32) -> bool {
+        self.matcher().matches(notes, amount)
+    }
+}
+
+// a rule with its pattern ready to match
+pub struct Matcher<'a> {
+    pub rule: &'a Rule,
+    pattern: String,
+    regex: Option<Regex>,
+}
+
+impl Matcher<'_> {
+    // text matching ignores case, so "grab" also matches "GRAB FOOD"
+    pub fn matches(&self, notes: &str, amount: f32) -> bool {
+        let text_matches = match self.rule.kind {
+            RuleKind::Contains => notes.to_lowercase().contains(&self.pattern),
+            RuleKind::Regex => self.regex.as_ref().is_some_and(|re| re.is_match(notes)),
+            RuleKind::Amount => true,
+        };
+        text_matches
+            && self.rule.min_amount.is_none_or(|min| amount >= min)
+            && self.rule.max_amount.is_none_or(|max| amount <= max)
+    }
+}
+
+// rules are tried in the order they were created, the first match wins
+pub fn first_match<'a>(matchers: &[Matcher<'a>], notes: &str, amount: f32) -> Option<&'a Rule> {
+    matchers
+        .iter()
+        .find(|m| m.matches(notes, amount))
+        .map(|m| m.rule)
+}
+
+// a record the rules moved to another category
+#[derive(Debug)]
+pub struct RuleChange {
+    pub record_id: Ulid,
+    pub notes: String,
+    pub category_name: String,
+}
+
+impl FromRow<'_, PgRow> for Rule {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let id: [u8; 16] = row.get("id");
+        let category_id: [u8; 16] = row.get("category_id");
+        let book_id: [u8; 16] = row.get("book_id");
+        let kind: String = row.get("kind");
+        let created_at: DateTime<Utc> = row.get("created_at");
+        let updated_at: Option<DateTime<Utc>> = row.try_get("updated_at").unwrap_or_default();
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
+
+        let res: Rule = Self {
+            id: Ulid::from_bytes(id),
+            created_at,
+            updated_at,
+            deleted_at,
+            book_id: Ulid::from_bytes(book_id),
+            category_id: Ulid::from_bytes(category_id),
+            category_name: row.try_get("category_name").unwrap_or_default(),
+            kind: RuleKind::parse(&kind),
+            pattern: row.get("pattern"),
+            min_amount: row.try_get("min_amount").unwrap_or_default(),
+            max_amount: row.try_get("max_amount").unwrap_or_default(),
+        };
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(kind: RuleKind, pattern: &str, min: Option<f32>, max: Option<f32>) -> Rule {
+        Rule::new(kind, pattern, min, max, Ulid::new(), Ulid::new()).unwrap()
+    }
+
+    #[test]
+    fn contains_ignores_case() {
+        let grab = rule(RuleKind::Contains, "grab", None, None);
+        assert!(grab.matches("GRAB FOOD lunch", 50000.0));
+        assert!(!grab.matches("gojek", 50000.0));
+    }
+
+    #[test]
+    fn regex_ignores_case() {
+        let coffee = rule(RuleKind::Regex, r"^(kopi|coffee)\b", None, None);
+        assert!(coffee.matches("Coffee with Budi", 30000.0));
+        assert!(coffee.matches("kopi susu", 30000.0));
+        assert!(!coffee.matches("iced coffee", 30000.0));
+    }
+
+    #[test]
+    fn amount_range_is_inclusive_and_narrows_text_rules() {
+        let small = rule(RuleKind::Amount, "ignored", Some(1000.0), Some(5000.0));
+        assert_eq!(small.pattern, "");
+        assert!(small.matches("anything", 1000.0));
+        assert!(small.matches("anything", 5000.0));
+        assert!(!small.matches("anything", 5001.0));
+        let big_grab = rule(RuleKind::Contains, "grab", Some(100000.0), None);
+        assert!(big_grab.matches("grab car", 150000.0));
+        assert!(!big_grab.matches("grab car", 50000.0));
+    }
+
+    #[test]
+    fn invalid_rules_are_refused() {
+        let new =
+            |kind, pattern, min, max| Rule::new(kind, pattern, min, max, Ulid::new(), Ulid::new());
+        assert_eq!(
+            new(RuleKind::Contains, "  ", None, None).unwrap_err(),
+            PATTERN_MISSING
+        );
+        assert_eq!(
+            new(RuleKind::Regex, "(", None, None).unwrap_err(),
+            PATTERN_INVALID
+        );
+        assert_eq!(
+            new(RuleKind::Amount, "", None, None).unwrap_err(),
+            RANGE_MISSING
+        );
+        assert_eq!(
+            new(RuleKind::Amount, "", Some(10.0), Some(5.0)).unwrap_err(),
+            RANGE_INVALID
+        );
+    }
+
+    #[test]
+    fn the_first_matching_rule_wins() {
+        let rules = [
+            rule(RuleKind::Contains, "grab", None, None),
+            rule(RuleKind::Contains, "food", None, None),
+        ];
+        let matchers: Vec<Matcher> = rules.iter().map(Rule::matcher).collect();
+        let found = first_match(&matchers, "grab food", 1.0).unwrap();
+        assert_eq!(found.id, rules[0].id);
+        assert!(first_match(&matchers, "rent", 1.0).is_none());
+    }
+}
@@ -50,14 +50,8 @@ impl FromRow<'_, PgRow> for Session {
         let issued_at: DateTime<Utc> = row.get("issued_at");
         let expire_at: DateTime<Utc> = row.get("expire_at");
 
-        let ip_address: Option<String> = match row.try_get("token") {
-            Ok(v) => v,
-            Err(_) => None,
-        };
-        let user_agent: Option<String> = match row.try_get("token") {
-            Ok(v) => v,
-            Err(_) => None,
-        };
+        let ip_address: Option<String> = row.try_get("token").unwrap_or_default();
+        let user_agent: Option<String> = row.try_get("token").unwrap_or_default();
 
         let res: Session = Self {
             session_id: Ulid::from_bytes(id),
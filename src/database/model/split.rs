@@ -0,0 +1,433 @@
+use chrono::serde::ts_milliseconds;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::prelude::FromRow;
+use sqlx::Row;
+use ulid::{serde::ulid_as_u128, Ulid};
+
+static NO_MEMBERS: &str = "choose at least one member to split with";
+static PERCENTAGE_NOT_FULL: &str = "percentages must add up to 100";
+static EXACT_NOT_FULL: &str = "exact shares must add up to the record amount";
+static EXACT_AMOUNT_CHANGED: &str =
+    "the record is split in exact amounts, split it again to change its amount";
+
+// amounts below this are treated as settled
+const TOLERANCE: f32 = 0.01;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum SplitMethod {
+    Equal,
+    Percentage,
+    Exact,
+}
+
+impl SplitMethod {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "percentage" => SplitMethod::Percentage,
+            "exact" => SplitMethod::Exact,
+            _ => SplitMethod::Equal,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SplitMethod::Equal => "equal",
+            SplitMethod::Percentage => "percentage",
+            SplitMethod::Exact => "exact",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Split {
+    #[serde(with = "ulid_as_u128")]
+    pub id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub record_id: Ulid,
+    // the member who paid the whole record
+    #[serde(with = "ulid_as_u128")]
+    pub paid_by: Ulid,
+    pub method: SplitMethod,
+
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Split {
+    pub fn new(record_id: Ulid, paid_by: Ulid, method: SplitMethod) -> Self {
+        let id = ulid::Ulid::new();
+        let created_at = chrono::offset::Utc::now();
+        Self {
+            id,
+            record_id,
+            paid_by,
+            method,
+            created_at,
+            deleted_at: None,
+        }
+    }
+
+    // turns the submitted value of every included member into its share of
+    // `amount`; values are ignored for equal splits, percentages for
+    // percentage splits and amounts for exact splits. The last member absorbs
+    // any rounding difference.
+    pub fn shares(&self, amount: f32, values: &[(Ulid, f32)]) -> Result<Vec<Share>, &'static str> {
+        if values.is_empty() {
+            return Err(NO_MEMBERS);
+        }
+        let amounts: Vec<f32> = match self.method {
+            SplitMethod::Equal => {
+                let each = (amount / values.len() as f32).floor();
+                values.iter().map(|_| each).collect()
+            }
+            SplitMethod::Percentage => {
+                let total: f32 = values.iter().map(|(_, v)| v).sum();
+                if (total - 100.0).abs() > TOLERANCE {
+                    return Err(PERCENTAGE_NOT_FULL);
+                }
+                values
+                    .iter()
+                    .map(|(_, v)| (amount * v / 100.0).floor())
+                    .collect()
+            }
+            SplitMethod::Exact => {
+                let total: f32 = values.iter().map(|(_, v)| v).sum();
+                if (total - amount).abs() > TOLERANCE {
+                    return Err(EXACT_NOT_FULL);
+                }
+                values.iter().map(|(_, v)| *v).collect()
+            }
+        };
+        let last = values.len() - 1;
+        let rest: f32 = amounts[..last].iter().sum();
+        Ok(values
+            .iter()
+            .zip(amounts)
+            .enumerate()
+            .map(|(i, ((account_id, _), share))| Share {
+                split_id: self.id,
+                account_id: *account_id,
+                amount: if i == last { amount - rest } else { share },
+            })
+            .collect())
+    }
+
+    // the shares once the record amount changes to `amount`: equal and
+    // percentage splits keep every member's part of the `current` shares,
+    // exact amounts were agreed on for the old amount and are refused
+    pub fn reshare(&self, amount: f32, current: &[Share]) -> Result<Vec<Share>, &'static str> {
+        if self.method == SplitMethod::Exact {
+            return Err(EXACT_AMOUNT_CHANGED);
+        }
+        let total: f32 = current.iter().map(|s| s.amount).sum();
+        let values: Vec<(Ulid, f32)> = current
+            .iter()
+            .map(|s| (s.account_id, s.amount / total * 100.0))
+            .collect();
+        self.shares(amount, &values)
+    }
+}
+
+impl FromRow<'_, PgRow> for Split {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let id: [u8; 16] = row.get("id");
+        let record_id: [u8; 16] = row.get("record_id");
+        let paid_by: [u8; 16] = row.get("paid_by");
+        let method: String = row.get("method");
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
+
+        let res: Split = Self {
+            id: Ulid::from_bytes(id),
+            record_id: Ulid::from_bytes(record_id),
+            paid_by: Ulid::from_bytes(paid_by),
+            method: SplitMethod::parse(&method),
+            created_at: row.get("created_at"),
+            deleted_at,
+        };
+        Ok(res)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Share {
+    #[serde(with = "ulid_as_u128")]
+    pub split_id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub account_id: Ulid,
+    pub amount: f32,
+}
+
+impl FromRow<'_, PgRow> for Share {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let split_id: [u8; 16] = row.get("split_id");
+        let account_id: [u8; 16] = row.get("account_id");
+        let res: Share = Self {
+            split_id: Ulid::from_bytes(split_id),
+            account_id: Ulid::from_bytes(account_id),
+            amount: row.get("amount"),
+        };
+        Ok(res)
+    }
+}
+
+// a reimbursement from one member to another
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Settlement {
+    #[serde(with = "ulid_as_u128")]
+    pub id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub book_id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub from_account_id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub to_account_id: Ulid,
+    pub amount: f32,
+
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Settlement {
+    pub fn new(book_id: Ulid, from_account_id: Ulid, to_account_id: Ulid, amount: f32) -> Self {
+        let id = ulid::Ulid::new();
+        let created_at = chrono::offset::Utc::now();
+        Self {
+            id,
+            book_id,
+            from_account_id,
+            to_account_id,
+            amount,
+            created_at,
+            deleted_at: None,
+        }
+    }
+}
+
+impl FromRow<'_, PgRow> for Settlement {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let id: [u8; 16] = row.get("id");
+        let book_id: [u8; 16] = row.get("book_id");
+        let from_account_id: [u8; 16] = row.get("from_account_id");
+        let to_account_id: [u8; 16] = row.get("to_account_id");
+        let deleted_at: Option<DateTime<Utc>> = row.try_get("deleted_at").unwrap_or_default();
+
+        let res: Settlement = Self {
+            id: Ulid::from_bytes(id),
+            book_id: Ulid::from_bytes(book_id),
+            from_account_id: Ulid::from_bytes(from_account_id),
+            to_account_id: Ulid::from_bytes(to_account_id),
+            amount: row.get("amount"),
+            created_at: row.get("created_at"),
+            deleted_at,
+        };
+        Ok(res)
+    }
+}
+
+// positive when the member is owed money, negative when the member owes
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct MemberBalance {
+    #[serde(with = "ulid_as_u128")]
+    pub account_id: Ulid,
+    pub net: f32,
+}
+
+impl FromRow<'_, PgRow> for MemberBalance {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        let account_id: [u8; 16] = row.get("account_id");
+        let res: MemberBalance = Self {
+            account_id: Ulid::from_bytes(account_id),
+            net: row.get("net"),
+        };
+        Ok(res)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Transfer {
+    #[serde(with = "ulid_as_u128")]
+    pub from_account_id: Ulid,
+    #[serde(with = "ulid_as_u128")]
+    pub to_account_id: Ulid,
+    pub amount: f32,
+}
+
+// settles the net balances by repeatedly matching the largest debtor with the
+// largest creditor; this greedy pass is not always the fewest transfers, but
+// needs at most one transfer less than the number of members with a balance
+pub fn simplify(balances: &[MemberBalance]) -> Vec<Transfer> {
+    let mut creditors: Vec<(Ulid, f32)> = balances
+        .iter()
+        .filter(|b| b.net > TOLERANCE)
+        .map(|b| (b.account_id, b.net))
+        .collect();
+    let mut debtors: Vec<(Ulid, f32)> = balances
+        .iter()
+        .filter(|b| b.net < -TOLERANCE)
+        .map(|b| (b.account_id, -b.net))
+        .collect();
+    let mut transfers: Vec<Transfer> = Vec::new();
+    loop {
+        creditors.sort_by(|a, b| b.1.total_cmp(&a.1));
+        debtors.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let (creditor, debtor) = match (creditors.first_mut(), debtors.first_mut()) {
+            (Some(c), Some(d)) if c.1 > TOLERANCE && d.1 > TOLERANCE => (c, d),
+            _ => break,
+        };
+        let amount = creditor.1.min(debtor.1);
+        creditor.1 -= amount;
+        debtor.1 -= amount;
+        transfers.push(Transfer {
+            from_account_id: debtor.0,
+            to_account_id: creditor.0,
+            amount,
+        });
+    }
+    transfers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members(count: usize) -> Vec<Ulid> {
+        (0..count).map(|_| Ulid::new()).collect()
+    }
+
+    fn amounts(shares: &[Share]) -> Vec<f32> {
+        shares.iter().map(|s| s.amount).collect()
+    }
+
+    fn balance(account_id: Ulid, net: f32) -> MemberBalance {
+        MemberBalance { account_id, net }
+    }
+
+    #[test]
+    fn equal_split_gives_the_remainder_to_the_last_member() {
+        let ids = members(3);
+        let split = Split::new(Ulid::new(), ids[0], SplitMethod::Equal);
+        let values: Vec<(Ulid, f32)> = ids.iter().map(|id| (*id, 0.0)).collect();
+        let shares = split.shares(100.0, &values).unwrap();
+        assert_eq!(amounts(&shares), vec![33.0, 33.0, 34.0]);
+        assert_eq!(shares[2].account_id, ids[2]);
+        assert!(shares.iter().all(|s| s.split_id == split.id));
+    }
+
+    #[test]
+    fn percentage_split_rounds_down_and_the_last_member_absorbs_the_rest() {
+        let ids = members(3);
+        let split = Split::new(Ulid::new(), ids[0], SplitMethod::Percentage);
+        let values = vec![(ids[0], 33.3), (ids[1], 33.3), (ids[2], 33.4)];
+        let shares = split.shares(1000.0, &values).unwrap();
+        assert_eq!(amounts(&shares), vec![333.0, 333.0, 334.0]);
+        assert_eq!(amounts(&shares).iter().sum::<f32>(), 1000.0);
+    }
+
+    #[test]
+    fn a_new_amount_keeps_every_members_part() {
+        let ids = members(2);
+        let split = Split::new(Ulid::new(), ids[0], SplitMethod::Percentage);
+        let shares = split
+            .shares(1000.0, &[(ids[0], 25.0), (ids[1], 75.0)])
+            .unwrap();
+        let shares = split.reshare(2000.0, &shares).unwrap();
+        assert_eq!(amounts(&shares), vec![500.0, 1500.0]);
+
+        let split = Split::new(Ulid::new(), ids[0], SplitMethod::Equal);
+        let shares = split
+            .shares(1000.0, &[(ids[0], 0.0), (ids[1], 0.0)])
+            .unwrap();
+        let shares = split.reshare(1001.0, &shares).unwrap();
+        assert_eq!(amounts(&shares), vec![500.0, 501.0]);
+    }
+
+    #[test]
+    fn exact_shares_are_not_reshared() {
+        let ids = members(2);
+        let split = Split::new(Ulid::new(), ids[0], SplitMethod::Exact);
+        let shares = split
+            .shares(900.0, &[(ids[0], 300.0), (ids[1], 600.0)])
+            .unwrap();
+        assert_eq!(
+            split.reshare(1000.0, &shares).unwrap_err(),
+            EXACT_AMOUNT_CHANGED
+        );
+    }
+
+    #[test]
+    fn percentages_must_add_up_to_a_hundred() {
+        let ids = members(2);
+        let split = Split::new(Ulid::new(), ids[0], SplitMethod::Percentage);
+        let values = vec![(ids[0], 50.0), (ids[1], 40.0)];
+        assert_eq!(
+            split.shares(1000.0, &values).unwrap_err(),
+            PERCENTAGE_NOT_FULL
+        );
+    }
+
+    #[test]
+    fn exact_shares_must_add_up_to_the_amount() {
+        let ids = members(2);
+        let split = Split::new(Ulid::new(), ids[0], SplitMethod::Exact);
+        let values = vec![(ids[0], 300.0), (ids[1], 600.0)];
+        assert_eq!(split.shares(1000.0, &values).unwrap_err(), EXACT_NOT_FULL);
+        let values = vec![(ids[0], 400.0), (ids[1], 600.0)];
+        let shares = split.shares(1000.0, &values).unwrap();
+        assert_eq!(amounts(&shares), vec![400.0, 600.0]);
+    }
+
+    #[test]
+    fn splits_need_a_member() {
+        let split = Split::new(Ulid::new(), Ulid::new(), SplitMethod::Equal);
+        assert_eq!(split.shares(1000.0, &[]).unwrap_err(), NO_MEMBERS);
+    }
+
+    #[test]
+    fn simplify_nets_balances_between_several_members() {
+        let ids = members(4);
+        let balances = vec![
+            balance(ids[0], 70.0),
+            balance(ids[1], 30.0),
+            balance(ids[2], -60.0),
+            balance(ids[3], -40.0),
+        ];
+        let transfers = simplify(&balances);
+        assert!(transfers.len() <= 3);
+        for (i, b) in balances.iter().enumerate() {
+            let received: f32 = transfers
+                .iter()
+                .filter(|t| t.to_account_id == ids[i])
+                .map(|t| t.amount)
+                .sum();
+            let sent: f32 = transfers
+                .iter()
+                .filter(|t| t.from_account_id == ids[i])
+                .map(|t| t.amount)
+                .sum();
+            assert!((received - sent - b.net).abs() < TOLERANCE);
+        }
+        // the largest debtor pays the largest creditor first
+        assert_eq!(transfers[0].from_account_id, ids[2]);
+        assert_eq!(transfers[0].to_account_id, ids[0]);
+        assert_eq!(transfers[0].amount, 60.0);
+    }
+
+    #[test]
+    fn simplify_skips_settled_members() {
+        let ids = members(3);
+        let balances = vec![
+            balance(ids[0], 50.0),
+            balance(ids[1], 0.001),
+            balance(ids[2], -50.0),
+        ];
+        let transfers = simplify(&balances);
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].from_account_id, ids[2]);
+        assert_eq!(transfers[0].to_account_id, ids[0]);
+        assert!(simplify(&[balance(ids[0], 0.0)]).is_empty());
+    }
+}
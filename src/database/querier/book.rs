@@ -1,8 +1,16 @@
-use crate::database::model::book::{AccountBook, Book};
-use sqlx::{postgres::PgRow, FromRow, PgPool, Postgres, Row};
+use crate::database::model::{
+    book::{Book, BookSettings, Member, Role},
+    category::Category,
+};
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, FromRow, PgPool, Postgres, Row, Transaction};
 use sqlx_core::error::BoxDynError;
+use std::collections::HashMap;
 use ulid::Ulid;
 
+// from the first instant up to, not including, the second
+pub type DateRange = (DateTime<Utc>, DateTime<Utc>);
+
 #[derive(Debug)]
 struct AccountBookCount {
     book_count: i64,
@@ -60,12 +68,167 @@ pub async fn get_by_id(pool: PgPool, id: Ulid) -> Option<Book> {
     }
 }
 
+// the settings of a live book, the defaults otherwise
+pub async fn get_settings(pool: &PgPool, id: Ulid) -> BookSettings {
+    match sqlx::query(
+        "SELECT timezone, month_start_day
+        FROM books
+        WHERE id = $1 AND deleted_at IS NULL;
+        ",
+    )
+    .bind(id.to_bytes())
+    .fetch_one(pool)
+    .await
+    {
+        Ok(v) => {
+            let timezone: String = v.get("timezone");
+            let month_start_day: i16 = v.get("month_start_day");
+            BookSettings::new(&timezone, month_start_day as u32)
+        }
+        Err(_) => BookSettings::default(),
+    }
+}
+
+pub async fn get_members(book_id: Ulid, pool: &PgPool) -> Vec<Member> {
+    match sqlx::query(
+        "SELECT ab.account_id, ab.role, a.email
+        FROM account_books ab
+        JOIN accounts a ON ab.account_id = a.id
+        WHERE ab.book_id = $1 AND ab.deleted_at IS NULL
+        ORDER BY a.email ASC;
+        ",
+    )
+    .bind(book_id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Member> = Vec::new();
+            for member in v {
+                let m = Member::from_row(&member).unwrap();
+                datas.push(m)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+// the role of the account in the book, none when it is not a member or either
+// has been removed
+pub async fn get_role(pool: &PgPool, book_id: Ulid, account_id: Ulid) -> Option<Role> {
+    match sqlx::query(
+        "SELECT ab.role
+        FROM account_books ab
+        JOIN books b ON ab.book_id = b.id
+        WHERE ab.book_id = $1 AND ab.account_id = $2
+            AND ab.deleted_at IS NULL AND b.deleted_at IS NULL;
+        ",
+    )
+    .bind(book_id.to_bytes())
+    .bind(account_id.to_bytes())
+    .fetch_one(pool)
+    .await
+    {
+        Ok(row) => Role::parse(row.get("role")),
+        Err(_) => None,
+    }
+}
+
+// the book the account works in unless it picks another, its saved default
+// while it is still a member there, otherwise its newest book
+pub async fn get_default(pool: &PgPool, account_id: Ulid) -> Option<Ulid> {
+    match sqlx::query(
+        "SELECT b.id
+        FROM books b
+        JOIN account_books ab ON b.id = ab.book_id
+        LEFT JOIN accounts a ON a.id = ab.account_id AND a.default_book_id = b.id
+        WHERE ab.account_id = $1 AND ab.deleted_at IS NULL AND b.deleted_at IS NULL
+        ORDER BY a.id IS NULL, b.id DESC
+        LIMIT 1;
+        ",
+    )
+    .bind(account_id.to_bytes())
+    .fetch_one(pool)
+    .await
+    {
+        Ok(row) => Some(Ulid::from_bytes(row.get("id"))),
+        Err(_) => None,
+    }
+}
+
+pub async fn set_default(
+    pool: &PgPool,
+    account_id: Ulid,
+    book_id: Ulid,
+) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "UPDATE accounts
+        SET default_book_id = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1",
+    )
+    .bind(account_id.to_bytes())
+    .bind(book_id.to_bytes())
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+// false when the change would demote the last owner of the book. Changes to
+// the members of a book wait for each other on the book row, so two owners
+// demoted at once cannot both see the other one still in place
+pub async fn set_role(
+    pool: &PgPool,
+    book_id: Ulid,
+    account_id: Ulid,
+    role: Role,
+) -> Result<bool, BoxDynError> {
+    let mut tx = pool.begin().await.unwrap();
+    if let Err(err) = sqlx::query("SELECT id FROM books WHERE id = $1 FOR UPDATE")
+        .bind(book_id.to_bytes())
+        .execute(&mut *tx)
+        .await
+    {
+        tx.rollback().await.unwrap();
+        return Err(Box::new(err));
+    }
+    match sqlx::query(
+        "UPDATE account_books
+        SET role = $3
+        WHERE book_id = $1 AND account_id = $2 AND deleted_at IS NULL
+            AND ($3 = 'owner' OR role <> 'owner' OR (
+                SELECT COUNT(*)
+                FROM account_books
+                WHERE book_id = $1 AND role = 'owner' AND deleted_at IS NULL
+            ) > 1)",
+    )
+    .bind(book_id.to_bytes())
+    .bind(account_id.to_bytes())
+    .bind(role.as_str())
+    .execute(&mut *tx)
+    .await
+    {
+        Ok(res) => {
+            tx.commit().await.unwrap();
+            Ok(res.rows_affected() > 0)
+        }
+        Err(err) => {
+            tx.rollback().await.unwrap();
+            Err(Box::new(err))
+        }
+    }
+}
+
 pub async fn get_count(pool: &PgPool, id: Ulid) -> i64 {
     match sqlx::query_as::<Postgres, AccountBookCount>(
         "
-        SELECT COUNT(book_id) as book_count
-        FROM account_books
-        WHERE account_id = $1;
+        SELECT COUNT(ab.book_id) as book_count
+        FROM account_books ab
+        JOIN books b ON ab.book_id = b.id
+        WHERE ab.account_id = $1 AND ab.deleted_at IS NULL AND b.deleted_at IS NULL;
     ",
     )
     .bind(id.to_bytes())
@@ -77,23 +240,55 @@ pub async fn get_count(pool: &PgPool, id: Ulid) -> i64 {
     }
 }
 
-pub async fn save(pool: &PgPool, account_id: Ulid, book: Book) -> Result<(), BoxDynError> {
+// creates the book for the account together with its starter categories
+pub async fn save(
+    pool: &PgPool,
+    account_id: Ulid,
+    book: Book,
+    categories: Vec<Category>,
+) -> Result<(), BoxDynError> {
     let mut tx = pool.begin().await.unwrap();
-    match sqlx::query("INSERT INTO books (id, name, description) VALUES ($1, $2, $3) RETURNING *;")
-        .bind(book.id.to_bytes())
-        .bind(book.name)
-        .bind(book.description)
-        .execute(&mut *tx)
-        .await
+    match sqlx::query(
+        "INSERT INTO books (id, name, description, timezone, month_start_day) VALUES ($1, $2, $3, $4, $5) RETURNING *;",
+    )
+    .bind(book.id.to_bytes())
+    .bind(book.name)
+    .bind(book.description)
+    .bind(book.timezone)
+    .bind(book.month_start_day as i16)
+    .execute(&mut *tx)
+    .await
     {
         Ok(_) => {
-            match sqlx::query("INSERT INTO account_books (account_id, book_id) VALUES ($1, $2)")
-                .bind(account_id.to_bytes())
-                .bind(book.id.to_bytes())
-                .execute(&mut *tx)
-                .await
+            match sqlx::query(
+                "INSERT INTO account_books (account_id, book_id, role) VALUES ($1, $2, $3)",
+            )
+            .bind(account_id.to_bytes())
+            .bind(book.id.to_bytes())
+            .bind(Role::Owner.as_str())
+            .execute(&mut *tx)
+            .await
             {
                 Ok(_) => {
+                    for c in categories {
+                        if let Err(e) = sqlx::query(
+                            "INSERT INTO categories (id, name, description, is_expense, book_id, parent_id, color, icon) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                        )
+                        .bind(c.id.to_bytes())
+                        .bind(c.name)
+                        .bind(c.description)
+                        .bind(c.is_expense)
+                        .bind(c.book_id.to_bytes())
+                        .bind(c.parent_id.map(|id| id.to_bytes()))
+                        .bind(c.color)
+                        .bind(c.icon)
+                        .execute(&mut *tx)
+                        .await
+                        {
+                            tx.rollback().await.unwrap();
+                            return Err(Box::new(e));
+                        }
+                    }
                     tx.commit().await.unwrap();
                     Ok(())
                 }
@@ -114,17 +309,21 @@ pub async fn edit(
     pool: &PgPool,
     name: String,
     desc: String,
+    settings: BookSettings,
     book_id: Ulid,
 ) -> Result<(), BoxDynError> {
     let mut tx = pool.begin().await.unwrap();
     match sqlx::query(
         "UPDATE books
-        SET name = $2, description = $3, updated_at = CURRENT_TIMESTAMP
+        SET name = $2, description = $3, timezone = $4, month_start_day = $5,
+            updated_at = CURRENT_TIMESTAMP
         WHERE id = $1",
     )
     .bind(book_id.to_bytes())
     .bind(name)
     .bind(desc)
+    .bind(settings.timezone.name())
+    .bind(settings.month_start_day as i16)
     .execute(&mut *tx)
     .await
     {
@@ -139,37 +338,42 @@ pub async fn edit(
     }
 }
 
-pub async fn add_owner_by_email(
+// takes the account out of the book, the book and everyone else in it stay;
+// false when the account is the last owner, who would leave the book without
+// anyone to run it
+pub async fn remove_member(
     pool: &PgPool,
     book_id: Ulid,
-    email: Vec<&str>,
-) -> Result<(), BoxDynError> {
+    account_id: Ulid,
+) -> Result<bool, BoxDynError> {
     let mut tx = pool.begin().await.unwrap();
-    match sqlx::query("SELECT id FROM accounts WHERE email = ANY($1)")
-        .bind(&email)
-        .fetch_all(&mut *tx)
+    // waits for other member changes of the book, see `set_role`
+    if let Err(err) = sqlx::query("SELECT id FROM books WHERE id = $1 FOR UPDATE")
+        .bind(book_id.to_bytes())
+        .execute(&mut *tx)
         .await
     {
-        Ok(accounts) => {
-            let mut datas: Vec<AccountBook> = Vec::new();
-            for account in accounts {
-                let id: [u8; 16] = account.get("id");
-                let id_account = Ulid::from_bytes(id);
-                datas.push(AccountBook {
-                    account_id: id_account,
-                    book_id,
-                });
-            }
-            for record in datas {
-                sqlx::query(
-                            "INSERT INTO account_books (account_id, book_id) VALUES ($1, $2) ON CONFLICT (account_id, book_id) DO NOTHING"
-                        ).bind(record.account_id.to_bytes())
-                        .bind(record.book_id.to_bytes())
-                        .execute(&mut *tx)
-                        .await.unwrap();
-            }
+        tx.rollback().await.unwrap();
+        return Err(Box::new(err));
+    }
+    match sqlx::query(
+        "UPDATE account_books
+        SET deleted_at = CURRENT_TIMESTAMP
+        WHERE book_id = $1 AND account_id = $2 AND deleted_at IS NULL
+            AND (role <> 'owner' OR (
+                SELECT COUNT(*)
+                FROM account_books
+                WHERE book_id = $1 AND role = 'owner' AND deleted_at IS NULL
+            ) > 1)",
+    )
+    .bind(book_id.to_bytes())
+    .bind(account_id.to_bytes())
+    .execute(&mut *tx)
+    .await
+    {
+        Ok(res) => {
             tx.commit().await.unwrap();
-            Ok(())
+            Ok(res.rows_affected() > 0)
         }
         Err(err) => {
             tx.rollback().await.unwrap();
@@ -178,6 +382,7 @@ pub async fn add_owner_by_email(
     }
 }
 
+// deletes the book for every member
 pub async fn delete(pool: &PgPool, book_id: Ulid, account_id: Ulid) -> Result<(), BoxDynError> {
     let mut tx = pool.begin().await.unwrap();
     match sqlx::query(
@@ -217,3 +422,187 @@ pub async fn delete(pool: &PgPool, book_id: Ulid, account_id: Ulid) -> Result<()
         }
     }
 }
+
+// copies what the book is made of into `book`: categories with their tree and
+// looks, payees, rules, members with their roles and the book settings, plus
+// the records created within `records` when given; nothing is written unless
+// all of it is
+pub async fn duplicate(
+    pool: &PgPool,
+    source_id: Ulid,
+    book: Book,
+    records: Option<DateRange>,
+) -> Result<(), BoxDynError> {
+    let mut tx = pool.begin().await.unwrap();
+    match copy_book(&mut tx, source_id, book, records).await {
+        Ok(_) => {
+            tx.commit().await.unwrap();
+            Ok(())
+        }
+        Err(e) => {
+            tx.rollback().await.unwrap();
+            Err(Box::new(e))
+        }
+    }
+}
+
+async fn copy_book(
+    tx: &mut Transaction<'_, Postgres>,
+    source_id: Ulid,
+    book: Book,
+    records: Option<DateRange>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO books (id, name, description, timezone, month_start_day)
+        SELECT $2, $3, $4, timezone, month_start_day
+        FROM books
+        WHERE id = $1",
+    )
+    .bind(source_id.to_bytes())
+    .bind(book.id.to_bytes())
+    .bind(&book.name)
+    .bind(&book.description)
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO account_books (account_id, book_id, role)
+        SELECT account_id, $2, role
+        FROM account_books
+        WHERE book_id = $1 AND deleted_at IS NULL",
+    )
+    .bind(source_id.to_bytes())
+    .bind(book.id.to_bytes())
+    .execute(&mut **tx)
+    .await?;
+
+    // new ids by old id, parents are linked once every category exists
+    let mut categories: HashMap<[u8; 16], Ulid> = HashMap::new();
+    let rows = sqlx::query(
+        "SELECT id, parent_id, name, description, is_expense, color, icon, archived_at
+        FROM categories
+        WHERE book_id = $1 AND deleted_at IS NULL",
+    )
+    .bind(source_id.to_bytes())
+    .fetch_all(&mut **tx)
+    .await?;
+    for row in &rows {
+        let id = Ulid::new();
+        categories.insert(row.get("id"), id);
+        sqlx::query(
+            "INSERT INTO categories (id, name, description, is_expense, book_id, color, icon, archived_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(id.to_bytes())
+        .bind(row.get::<String, _>("name"))
+        .bind(row.get::<String, _>("description"))
+        .bind(row.get::<bool, _>("is_expense"))
+        .bind(book.id.to_bytes())
+        .bind(row.get::<String, _>("color"))
+        .bind(row.get::<String, _>("icon"))
+        .bind(row.get::<Option<DateTime<Utc>>, _>("archived_at"))
+        .execute(&mut **tx)
+        .await?;
+    }
+    for row in &rows {
+        let parent = row
+            .get::<Option<[u8; 16]>, _>("parent_id")
+            .and_then(|p| categories.get(&p));
+        if let Some(parent) = parent {
+            sqlx::query("UPDATE categories SET parent_id = $2 WHERE id = $1")
+                .bind(categories[&row.get::<[u8; 16], _>("id")].to_bytes())
+                .bind(parent.to_bytes())
+                .execute(&mut **tx)
+                .await?;
+        }
+    }
+    let category = |old: Option<[u8; 16]>| old.and_then(|c| categories.get(&c)).copied();
+
+    let mut payees: HashMap<[u8; 16], Ulid> = HashMap::new();
+    let rows = sqlx::query(
+        "SELECT id, name, default_category_id
+        FROM payees
+        WHERE book_id = $1 AND deleted_at IS NULL",
+    )
+    .bind(source_id.to_bytes())
+    .fetch_all(&mut **tx)
+    .await?;
+    for row in rows {
+        let id = Ulid::new();
+        payees.insert(row.get("id"), id);
+        sqlx::query(
+            "INSERT INTO payees (id, name, default_category_id, book_id) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(id.to_bytes())
+        .bind(row.get::<String, _>("name"))
+        .bind(category(row.get("default_category_id")).map(|c| c.to_bytes()))
+        .bind(book.id.to_bytes())
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    let rows = sqlx::query(
+        "SELECT kind, pattern, min_amount, max_amount, category_id
+        FROM rules
+        WHERE book_id = $1 AND deleted_at IS NULL
+        ORDER BY id ASC",
+    )
+    .bind(source_id.to_bytes())
+    .fetch_all(&mut **tx)
+    .await?;
+    for row in rows {
+        let Some(category_id) = category(row.get("category_id")) else {
+            continue;
+        };
+        sqlx::query(
+            "INSERT INTO rules (id, kind, pattern, min_amount, max_amount, category_id, book_id) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(Ulid::new().to_bytes())
+        .bind(row.get::<String, _>("kind"))
+        .bind(row.get::<String, _>("pattern"))
+        .bind(row.get::<Option<f32>, _>("min_amount"))
+        .bind(row.get::<Option<f32>, _>("max_amount"))
+        .bind(category_id.to_bytes())
+        .bind(book.id.to_bytes())
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    if let Some((from, to)) = records {
+        let rows = sqlx::query(
+            "SELECT created_at, notes, amount, category_id, payee_id, created_by,
+                category_from_rule
+            FROM records
+            WHERE book_id = $1 AND deleted_at IS NULL
+                AND created_at >= $2 AND created_at < $3",
+        )
+        .bind(source_id.to_bytes())
+        .bind(from)
+        .bind(to)
+        .fetch_all(&mut **tx)
+        .await?;
+        for row in rows {
+            let Some(category_id) = category(row.get("category_id")) else {
+                continue;
+            };
+            let payee_id = row
+                .get::<Option<[u8; 16]>, _>("payee_id")
+                .and_then(|p| payees.get(&p));
+            // ids sort records by time, so the copy keeps the original's
+            let created_at: DateTime<Utc> = row.get("created_at");
+            sqlx::query(
+                "INSERT INTO records (id, created_at, notes, amount, category_id, book_id, payee_id, created_by, category_from_rule) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+            )
+            .bind(Ulid::from_datetime(created_at.into()).to_bytes())
+            .bind(created_at)
+            .bind(row.get::<String, _>("notes"))
+            .bind(row.get::<f32, _>("amount"))
+            .bind(category_id.to_bytes())
+            .bind(book.id.to_bytes())
+            .bind(payee_id.map(|p| p.to_bytes()))
+            .bind(row.get::<Option<[u8; 16]>, _>("created_by"))
+            .bind(row.get::<bool, _>("category_from_rule"))
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use crate::database::model::category::Category;
+use crate::database::model::category::{sort_by_usage, Category, CategorySuggestion};
 use sqlx::{FromRow, PgPool};
 use sqlx_core::error::BoxDynError;
 use ulid::Ulid;
@@ -6,13 +6,16 @@ use ulid::Ulid;
 pub async fn save(pool: &PgPool, c: Category) -> Result<(), BoxDynError> {
     let mut tx = pool.begin().await.unwrap();
     match sqlx::query(
-        "INSERT INTO categories (id, name, description, is_expense, book_id) VALUES ($1, $2, $3, $4, $5) RETURNING *;",
+        "INSERT INTO categories (id, name, description, is_expense, book_id, parent_id, color, icon) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *;",
     )
     .bind(c.id.to_bytes())
     .bind(c.name)
     .bind(c.description)
     .bind(c.is_expense)
     .bind(c.book_id.to_bytes())
+    .bind(c.parent_id.map(|id| id.to_bytes()))
+    .bind(c.color)
+    .bind(c.icon)
     .execute(&mut *tx)
     .await{
         Ok(_) => {
@@ -26,43 +29,182 @@ pub async fn save(pool: &PgPool, c: Category) -> Result<(), BoxDynError> {
     }
 }
 
-pub async fn edit(
+// writes the category only while it is still at `c.version`, false otherwise;
+// a new type applies to the whole subtree, sub-categories share their parent's
+pub async fn edit(pool: &PgPool, c: Category) -> Result<bool, BoxDynError> {
+    let mut tx = pool.begin().await.unwrap();
+    match sqlx::query(
+        "UPDATE categories
+        SET name = $2, description = $3, parent_id = $4, color = $5, icon = $6,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND version = $7",
+    )
+    .bind(c.id.to_bytes())
+    .bind(c.name)
+    .bind(c.description)
+    .bind(c.parent_id.map(|id| id.to_bytes()))
+    .bind(c.color)
+    .bind(c.icon)
+    .bind(c.version)
+    .execute(&mut *tx)
+    .await
+    {
+        Ok(v) if v.rows_affected() == 0 => {
+            tx.rollback().await.unwrap();
+            Ok(false)
+        }
+        Ok(_) => {
+            if let Err(e) = sqlx::query(
+                "WITH RECURSIVE subtree AS (
+                    SELECT id FROM categories WHERE id = $1
+                    UNION ALL
+                    SELECT categories.id
+                    FROM categories
+                    JOIN subtree ON categories.parent_id = subtree.id
+                )
+                UPDATE categories
+                SET is_expense = $2, updated_at = CURRENT_TIMESTAMP
+                WHERE id IN (SELECT id FROM subtree) AND is_expense <> $2",
+            )
+            .bind(c.id.to_bytes())
+            .bind(c.is_expense)
+            .execute(&mut *tx)
+            .await
+            {
+                tx.rollback().await.unwrap();
+                return Err(Box::new(e));
+            }
+            tx.commit().await.unwrap();
+            Ok(true)
+        }
+        Err(e) => {
+            tx.rollback().await.unwrap();
+            Err(Box::new(e))
+        }
+    }
+}
+
+// records and recurring items of a deleted category move to the replacement
+// when one is given and are soft-deleted with it otherwise; sub-categories
+// move up to its parent
+pub async fn delete(
     pool: &PgPool,
-    name: String,
-    desc: String,
     category_id: Ulid,
+    replacement_id: Option<Ulid>,
 ) -> Result<(), BoxDynError> {
     let mut tx = pool.begin().await.unwrap();
+    let statements = match replacement_id {
+        Some(_) => [
+            "UPDATE records
+            SET category_id = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE category_id = $1",
+            "UPDATE recurrings
+            SET category_id = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE category_id = $1",
+            "UPDATE rules
+            SET category_id = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE category_id = $1",
+            "UPDATE payees
+            SET default_category_id = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE default_category_id = $1",
+        ],
+        None => [
+            "UPDATE records
+            SET deleted_at = CURRENT_TIMESTAMP
+            WHERE category_id = $1 AND deleted_at IS NULL",
+            "UPDATE recurrings
+            SET deleted_at = CURRENT_TIMESTAMP
+            WHERE category_id = $1 AND deleted_at IS NULL",
+            "UPDATE rules
+            SET deleted_at = CURRENT_TIMESTAMP
+            WHERE category_id = $1 AND deleted_at IS NULL",
+            "UPDATE payees
+            SET default_category_id = NULL, updated_at = CURRENT_TIMESTAMP
+            WHERE default_category_id = $1",
+        ],
+    };
+    for statement in statements {
+        let mut query = sqlx::query(statement).bind(category_id.to_bytes());
+        if let Some(replacement) = replacement_id {
+            query = query.bind(replacement.to_bytes());
+        }
+        if let Err(err) = query.execute(&mut *tx).await {
+            tx.rollback().await.unwrap();
+            return Err(Box::new(err));
+        }
+    }
     match sqlx::query(
         "UPDATE categories
-        SET name = $2, description = $3, updated_at = CURRENT_TIMESTAMP
+        SET deleted_at = CURRENT_TIMESTAMP
         WHERE id = $1",
     )
     .bind(category_id.to_bytes())
-    .bind(name)
-    .bind(desc)
     .execute(&mut *tx)
     .await
     {
         Ok(_) => {
+            if let Err(err) = sqlx::query(
+                "UPDATE categories
+                SET parent_id = (SELECT parent_id FROM categories WHERE id = $1),
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE parent_id = $1",
+            )
+            .bind(category_id.to_bytes())
+            .execute(&mut *tx)
+            .await
+            {
+                tx.rollback().await.unwrap();
+                return Err(Box::new(err));
+            }
             tx.commit().await.unwrap();
             Ok(())
         }
-        Err(e) => {
+        Err(err) => {
             tx.rollback().await.unwrap();
-            Err(Box::new(e))
+            Err(Box::new(err))
         }
     }
 }
 
-pub async fn delete(pool: &PgPool, category_id: Ulid) -> Result<(), BoxDynError> {
+// moves everything that points at the source category to the target, then
+// soft-deletes the source; the caller makes sure both are in the same book and
+// of the same type
+pub async fn merge(pool: &PgPool, source_id: Ulid, target_id: Ulid) -> Result<(), BoxDynError> {
     let mut tx = pool.begin().await.unwrap();
+    let statements = [
+        "UPDATE records
+        SET category_id = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE category_id = $1",
+        "UPDATE recurrings
+        SET category_id = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE category_id = $1",
+        "UPDATE rules
+        SET category_id = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE category_id = $1",
+        "UPDATE payees
+        SET default_category_id = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE default_category_id = $1",
+        "UPDATE categories
+        SET parent_id = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE parent_id = $1",
+    ];
+    for statement in statements {
+        if let Err(err) = sqlx::query(statement)
+            .bind(source_id.to_bytes())
+            .bind(target_id.to_bytes())
+            .execute(&mut *tx)
+            .await
+        {
+            tx.rollback().await.unwrap();
+            return Err(Box::new(err));
+        }
+    }
     match sqlx::query(
         "UPDATE categories
         SET deleted_at = CURRENT_TIMESTAMP
         WHERE id = $1",
     )
-    .bind(category_id.to_bytes())
+    .bind(source_id.to_bytes())
     .execute(&mut *tx)
     .await
     {
@@ -77,9 +219,39 @@ pub async fn delete(pool: &PgPool, category_id: Ulid) -> Result<(), BoxDynError>
     }
 }
 
+// archiving or restoring a category does the same to its subtree, so an
+// archived parent never shows live sub-categories
+pub async fn archive(pool: &PgPool, category_id: Ulid, archived: bool) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "WITH RECURSIVE subtree AS (
+            SELECT id FROM categories WHERE id = $1
+            UNION ALL
+            SELECT categories.id
+            FROM categories
+            JOIN subtree ON categories.parent_id = subtree.id
+        )
+        UPDATE categories
+        SET archived_at = CASE WHEN $2 THEN CURRENT_TIMESTAMP END,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id IN (SELECT id FROM subtree) AND (archived_at IS NULL) = $2",
+    )
+    .bind(category_id.to_bytes())
+    .bind(archived)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
 pub async fn get_by_book_id(id: Ulid, pool: PgPool) -> Vec<Category> {
     match sqlx::query(
-        "SELECT *
+        "SELECT categories.*, (
+                SELECT COUNT(*)
+                FROM records
+                WHERE records.category_id = categories.id AND records.deleted_at IS NULL
+            ) AS record_count
             FROM categories
             WHERE book_id = $1 AND deleted_at IS NULL
             ORDER BY id DESC;
@@ -101,9 +273,81 @@ pub async fn get_by_book_id(id: Ulid, pool: PgPool) -> Vec<Category> {
     }
 }
 
+// like `get_by_book_id` with a usage score per category: every record counts
+// for one when it is new and half as much for every 30 days of age
+pub async fn get_by_usage(id: Ulid, pool: &PgPool) -> Vec<Category> {
+    match sqlx::query(
+        "SELECT categories.*, COUNT(records.id) AS record_count,
+                COALESCE(SUM(POWER(0.5,
+                    EXTRACT(EPOCH FROM (CURRENT_TIMESTAMP - records.created_at)) / 2592000.0
+                )), 0)::FLOAT4 AS usage_score
+            FROM categories
+            LEFT JOIN records ON records.category_id = categories.id
+                AND records.deleted_at IS NULL
+            WHERE categories.book_id = $1 AND categories.deleted_at IS NULL
+            GROUP BY categories.id
+            ORDER BY categories.id DESC;
+        ",
+    )
+    .bind(id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Category> = Vec::new();
+            for category in v {
+                let b = Category::from_row(&category).unwrap();
+                datas.push(b)
+            }
+            sort_by_usage(&mut datas);
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+// categories of the book used by earlier records whose notes contain any of
+// the keywords, most used first
+pub async fn suggest(id: Ulid, keywords: &[String], pool: &PgPool) -> Vec<CategorySuggestion> {
+    let patterns: Vec<String> = keywords.iter().map(|k| format!("%{}%", k)).collect();
+    match sqlx::query(
+        "SELECT categories.id AS category_id, categories.name, COUNT(records.id) AS record_count
+            FROM records
+            JOIN categories ON records.category_id = categories.id
+            WHERE records.book_id = $1
+                AND records.deleted_at IS NULL
+                AND categories.deleted_at IS NULL
+                AND categories.archived_at IS NULL
+                AND records.notes ILIKE ANY($2)
+            GROUP BY categories.id, categories.name
+            ORDER BY record_count DESC, MAX(records.created_at) DESC
+            LIMIT 3;
+        ",
+    )
+    .bind(id.to_bytes())
+    .bind(&patterns)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<CategorySuggestion> = Vec::new();
+            for row in v {
+                let s = CategorySuggestion::from_row(&row).unwrap();
+                datas.push(s)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
 pub async fn get_by_id(id: Ulid, pool: PgPool) -> Option<Category> {
     match sqlx::query(
-        "SELECT *
+        "SELECT categories.*, (
+                SELECT COUNT(*)
+                FROM records
+                WHERE records.category_id = categories.id AND records.deleted_at IS NULL
+            ) AS record_count
             FROM categories
             WHERE id = $1 AND deleted_at IS NULL;
         ",
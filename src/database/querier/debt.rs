@@ -0,0 +1,228 @@
+use sqlx::{FromRow, PgPool};
+use sqlx_core::error::BoxDynError;
+use ulid::Ulid;
+
+use crate::database::model::debt::{Debt, DebtPayment, Installment};
+
+// payments count with the current amount of their linked record
+static DEBT_SELECT: &str = "
+    SELECT debts.*, COALESCE((
+        SELECT SUM(records.amount)
+        FROM debt_payments
+        JOIN records ON debt_payments.record_id = records.id
+        WHERE debt_payments.debt_id = debts.id
+            AND debt_payments.deleted_at IS NULL
+            AND records.deleted_at IS NULL
+    ), 0)::FLOAT4 AS paid
+    FROM debts
+";
+
+pub async fn save(
+    pool: &PgPool,
+    d: Debt,
+    installments: Vec<Installment>,
+) -> Result<(), BoxDynError> {
+    let mut tx = pool.begin().await.unwrap();
+    match sqlx::query(
+        "INSERT INTO debts (id, counterparty, notes, principal, is_receivable, book_id) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *;",
+    )
+    .bind(d.id.to_bytes())
+    .bind(d.counterparty)
+    .bind(d.notes)
+    .bind(d.principal)
+    .bind(d.is_receivable)
+    .bind(d.book_id.to_bytes())
+    .execute(&mut *tx)
+    .await
+    {
+        Ok(_) => {
+            for i in installments {
+                if let Err(err) = sqlx::query(
+                    "INSERT INTO debt_installments (id, due_date, amount, debt_id) VALUES ($1, $2, $3, $4)",
+                )
+                .bind(i.id.to_bytes())
+                .bind(i.due_date)
+                .bind(i.amount)
+                .bind(i.debt_id.to_bytes())
+                .execute(&mut *tx)
+                .await
+                {
+                    tx.rollback().await.unwrap();
+                    return Err(Box::new(err));
+                }
+            }
+            tx.commit().await.unwrap();
+            Ok(())
+        }
+        Err(e) => {
+            tx.rollback().await.unwrap();
+            Err(Box::new(e))
+        }
+    }
+}
+
+pub async fn delete(pool: &PgPool, debt_id: Ulid) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "UPDATE debts
+        SET deleted_at = CURRENT_TIMESTAMP
+        WHERE id = $1",
+    )
+    .bind(debt_id.to_bytes())
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn get_by_book_id(id: Ulid, pool: &PgPool) -> Vec<Debt> {
+    match sqlx::query(&format!(
+        "{} WHERE debts.book_id = $1 AND debts.deleted_at IS NULL
+        ORDER BY debts.id DESC;",
+        DEBT_SELECT
+    ))
+    .bind(id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Debt> = Vec::new();
+            for row in v {
+                let d = Debt::from_row(&row).unwrap();
+                datas.push(d)
+            }
+            // open debts first, settled ones after
+            datas.sort_by_key(|d| d.is_settled());
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+pub async fn get_by_id(id: Ulid, pool: &PgPool) -> Option<Debt> {
+    match sqlx::query(&format!(
+        "{} WHERE debts.id = $1 AND debts.deleted_at IS NULL;",
+        DEBT_SELECT
+    ))
+    .bind(id.to_bytes())
+    .fetch_one(pool)
+    .await
+    {
+        Ok(v) => {
+            let debt = Debt::from_row(&v).unwrap();
+            Some(debt)
+        }
+        Err(_) => None,
+    }
+}
+
+pub async fn get_installments(debt_id: Ulid, pool: &PgPool) -> Vec<Installment> {
+    match sqlx::query("SELECT * FROM debt_installments WHERE debt_id = $1 ORDER BY due_date ASC;")
+        .bind(debt_id.to_bytes())
+        .fetch_all(pool)
+        .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Installment> = Vec::new();
+            for row in v {
+                let i = Installment::from_row(&row).unwrap();
+                datas.push(i)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+// installments of every debt in the book, ordered by due date
+pub async fn get_installments_by_book_id(id: Ulid, pool: &PgPool) -> Vec<Installment> {
+    match sqlx::query(
+        "
+        SELECT debt_installments.*
+        FROM debt_installments
+        JOIN debts ON debt_installments.debt_id = debts.id
+        WHERE debts.book_id = $1 AND debts.deleted_at IS NULL
+        ORDER BY debt_installments.due_date ASC;
+    ",
+    )
+    .bind(id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Installment> = Vec::new();
+            for row in v {
+                let i = Installment::from_row(&row).unwrap();
+                datas.push(i)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+pub async fn get_payments(debt_id: Ulid, pool: &PgPool) -> Vec<DebtPayment> {
+    match sqlx::query(
+        "
+        SELECT debt_payments.*, records.notes, records.amount
+        FROM debt_payments
+        JOIN records ON debt_payments.record_id = records.id
+        WHERE debt_payments.debt_id = $1
+            AND debt_payments.deleted_at IS NULL
+            AND records.deleted_at IS NULL
+        ORDER BY debt_payments.id DESC;
+    ",
+    )
+    .bind(debt_id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<DebtPayment> = Vec::new();
+            for row in v {
+                let p = DebtPayment::from_row(&row).unwrap();
+                datas.push(p)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+// false when the record already pays towards the debt
+pub async fn add_payment(pool: &PgPool, p: DebtPayment) -> Result<bool, BoxDynError> {
+    match sqlx::query(
+        "INSERT INTO debt_payments (id, debt_id, record_id) VALUES ($1, $2, $3)
+        ON CONFLICT (debt_id, record_id) WHERE deleted_at IS NULL DO NOTHING",
+    )
+    .bind(p.id.to_bytes())
+    .bind(p.debt_id.to_bytes())
+    .bind(p.record_id.to_bytes())
+    .execute(pool)
+    .await
+    {
+        Ok(res) => Ok(res.rows_affected() > 0),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn delete_payment(
+    pool: &PgPool,
+    debt_id: Ulid,
+    payment_id: Ulid,
+) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "UPDATE debt_payments
+        SET deleted_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND debt_id = $2",
+    )
+    .bind(payment_id.to_bytes())
+    .bind(debt_id.to_bytes())
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
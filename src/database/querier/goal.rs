@@ -0,0 +1,156 @@
+use sqlx::{FromRow, PgPool};
+use sqlx_core::error::BoxDynError;
+use ulid::Ulid;
+
+use crate::database::model::goal::{Contribution, Goal};
+
+// linked records count with their current amount, and stop counting once deleted
+static GOAL_SELECT: &str = "
+    SELECT goals.*, COALESCE((
+        SELECT SUM(COALESCE(records.amount, goal_contributions.amount))
+        FROM goal_contributions
+        LEFT JOIN records ON goal_contributions.record_id = records.id
+        WHERE goal_contributions.goal_id = goals.id
+            AND goal_contributions.deleted_at IS NULL
+            AND (goal_contributions.record_id IS NULL OR records.deleted_at IS NULL)
+    ), 0)::FLOAT4 AS saved
+    FROM goals
+";
+
+pub async fn save(pool: &PgPool, g: Goal) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "INSERT INTO goals (id, name, target_amount, target_date, book_id) VALUES ($1, $2, $3, $4, $5) RETURNING *;",
+    )
+    .bind(g.id.to_bytes())
+    .bind(g.name)
+    .bind(g.target_amount)
+    .bind(g.target_date)
+    .bind(g.book_id.to_bytes())
+    .execute(pool)
+    .await {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn delete(pool: &PgPool, goal_id: Ulid) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "UPDATE goals
+        SET deleted_at = CURRENT_TIMESTAMP
+        WHERE id = $1",
+    )
+    .bind(goal_id.to_bytes())
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn get_by_book_id(id: Ulid, pool: &PgPool) -> Vec<Goal> {
+    match sqlx::query(&format!(
+        "{} WHERE goals.book_id = $1 AND goals.deleted_at IS NULL ORDER BY goals.target_date ASC;",
+        GOAL_SELECT
+    ))
+    .bind(id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Goal> = Vec::new();
+            for row in v {
+                let g = Goal::from_row(&row).unwrap();
+                datas.push(g)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+pub async fn get_by_id(id: Ulid, pool: &PgPool) -> Option<Goal> {
+    match sqlx::query(&format!(
+        "{} WHERE goals.id = $1 AND goals.deleted_at IS NULL;",
+        GOAL_SELECT
+    ))
+    .bind(id.to_bytes())
+    .fetch_one(pool)
+    .await
+    {
+        Ok(v) => {
+            let goal = Goal::from_row(&v).unwrap();
+            Some(goal)
+        }
+        Err(_) => None,
+    }
+}
+
+// false when the record already counts towards the goal
+pub async fn add_contribution(pool: &PgPool, c: Contribution) -> Result<bool, BoxDynError> {
+    match sqlx::query(
+        "INSERT INTO goal_contributions (id, notes, amount, goal_id, record_id) VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (goal_id, record_id) WHERE deleted_at IS NULL DO NOTHING;",
+    )
+    .bind(c.id.to_bytes())
+    .bind(c.notes)
+    .bind(c.amount)
+    .bind(c.goal_id.to_bytes())
+    .bind(c.record_id.map(|id| id.to_bytes()))
+    .execute(pool)
+    .await {
+        Ok(res) => Ok(res.rows_affected() > 0),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn delete_contribution(
+    pool: &PgPool,
+    goal_id: Ulid,
+    contribution_id: Ulid,
+) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "UPDATE goal_contributions
+        SET deleted_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND goal_id = $2",
+    )
+    .bind(contribution_id.to_bytes())
+    .bind(goal_id.to_bytes())
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn get_contributions(goal_id: Ulid, pool: &PgPool) -> Vec<Contribution> {
+    match sqlx::query(
+        "
+        SELECT goal_contributions.id, goal_contributions.goal_id, goal_contributions.record_id,
+            goal_contributions.created_at, goal_contributions.deleted_at,
+            COALESCE(records.notes, goal_contributions.notes) AS notes,
+            COALESCE(records.amount, goal_contributions.amount) AS amount
+        FROM goal_contributions
+        LEFT JOIN records ON goal_contributions.record_id = records.id
+        WHERE goal_contributions.goal_id = $1
+            AND goal_contributions.deleted_at IS NULL
+            AND (goal_contributions.record_id IS NULL OR records.deleted_at IS NULL)
+        ORDER BY goal_contributions.id DESC;
+    ",
+    )
+    .bind(goal_id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Contribution> = Vec::new();
+            for row in v {
+                let c = Contribution::from_row(&row).unwrap();
+                datas.push(c)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
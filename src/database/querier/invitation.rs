@@ -0,0 +1,175 @@
+use sqlx::{FromRow, PgPool};
+use sqlx_core::error::BoxDynError;
+use ulid::Ulid;
+
+use crate::database::model::invitation::{Invitation, InvitationStatus};
+
+// saves the invitations, replacing any invitation still pending for the same
+// email in the same book
+pub async fn save(pool: &PgPool, invitations: Vec<Invitation>) -> Result<(), BoxDynError> {
+    let mut tx = pool.begin().await.unwrap();
+    for i in invitations {
+        if let Err(e) = sqlx::query(
+            "UPDATE book_invitations
+            SET deleted_at = CURRENT_TIMESTAMP
+            WHERE book_id = $1 AND LOWER(email) = LOWER($2)
+                AND status = 'pending' AND deleted_at IS NULL",
+        )
+        .bind(i.book_id.to_bytes())
+        .bind(&i.email)
+        .execute(&mut *tx)
+        .await
+        {
+            tx.rollback().await.unwrap();
+            return Err(Box::new(e));
+        }
+        if let Err(e) = sqlx::query(
+            "INSERT INTO book_invitations (id, token, email, role, status, expires_at, book_id, invited_by) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(i.id.to_bytes())
+        .bind(&i.token)
+        .bind(&i.email)
+        .bind(i.role.as_str())
+        .bind(i.status.as_str())
+        .bind(i.expires_at)
+        .bind(i.book_id.to_bytes())
+        .bind(i.invited_by.to_bytes())
+        .execute(&mut *tx)
+        .await
+        {
+            tx.rollback().await.unwrap();
+            return Err(Box::new(e));
+        }
+    }
+    tx.commit().await.unwrap();
+    Ok(())
+}
+
+// invitations the account can still answer, matched by the email it signed
+// up with
+pub async fn get_pending_by_account(account_id: Ulid, pool: &PgPool) -> Vec<Invitation> {
+    match sqlx::query(
+        "
+        SELECT i.*, b.name AS book_name
+        FROM book_invitations i
+        JOIN accounts a ON LOWER(a.email) = LOWER(i.email)
+        JOIN books b ON i.book_id = b.id
+        WHERE a.id = $1 AND i.status = 'pending' AND i.deleted_at IS NULL
+            AND i.expires_at > CURRENT_TIMESTAMP AND b.deleted_at IS NULL
+        ORDER BY i.id DESC;
+    ",
+    )
+    .bind(account_id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Invitation> = Vec::new();
+            for row in v {
+                let i = Invitation::from_row(&row).unwrap();
+                datas.push(i)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+pub async fn get_pending_by_book(book_id: Ulid, pool: &PgPool) -> Vec<Invitation> {
+    match sqlx::query(
+        "
+        SELECT *
+        FROM book_invitations
+        WHERE book_id = $1 AND status = 'pending' AND deleted_at IS NULL
+            AND expires_at > CURRENT_TIMESTAMP
+        ORDER BY email ASC;
+    ",
+    )
+    .bind(book_id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Invitation> = Vec::new();
+            for row in v {
+                let i = Invitation::from_row(&row).unwrap();
+                datas.push(i)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+// the pending invitation with the token, only when it was sent to the email
+// of the account and has not expired
+pub async fn get_for_account(token: &str, account_id: Ulid, pool: &PgPool) -> Option<Invitation> {
+    match sqlx::query(
+        "
+        SELECT i.*
+        FROM book_invitations i
+        JOIN accounts a ON LOWER(a.email) = LOWER(i.email)
+        WHERE i.token = $1 AND a.id = $2 AND i.status = 'pending'
+            AND i.deleted_at IS NULL AND i.expires_at > CURRENT_TIMESTAMP;
+    ",
+    )
+    .bind(token)
+    .bind(account_id.to_bytes())
+    .fetch_one(pool)
+    .await
+    {
+        Ok(row) => Some(Invitation::from_row(&row).unwrap()),
+        Err(_) => None,
+    }
+}
+
+// records the answer, accepting also makes the account a member with the
+// invited role; someone who is already a member keeps their role. False when
+// the invitation was answered in the meantime
+pub async fn respond(
+    pool: &PgPool,
+    invitation: &Invitation,
+    account_id: Ulid,
+    status: InvitationStatus,
+) -> Result<bool, BoxDynError> {
+    let mut tx = pool.begin().await.unwrap();
+    match sqlx::query(
+        "UPDATE book_invitations
+        SET status = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND status = 'pending'",
+    )
+    .bind(invitation.id.to_bytes())
+    .bind(status.as_str())
+    .execute(&mut *tx)
+    .await
+    {
+        Ok(res) if res.rows_affected() == 1 => {}
+        Ok(_) => {
+            tx.rollback().await.unwrap();
+            return Ok(false);
+        }
+        Err(e) => {
+            tx.rollback().await.unwrap();
+            return Err(Box::new(e));
+        }
+    }
+    if status == InvitationStatus::Accepted {
+        if let Err(e) = sqlx::query(
+            "INSERT INTO account_books (account_id, book_id, role) VALUES ($1, $2, $3)
+            ON CONFLICT (account_id, book_id) DO UPDATE
+            SET role = EXCLUDED.role, deleted_at = NULL
+            WHERE account_books.deleted_at IS NOT NULL",
+        )
+        .bind(account_id.to_bytes())
+        .bind(invitation.book_id.to_bytes())
+        .bind(invitation.role.as_str())
+        .execute(&mut *tx)
+        .await
+        {
+            tx.rollback().await.unwrap();
+            return Err(Box::new(e));
+        }
+    }
+    tx.commit().await.unwrap();
+    Ok(true)
+}
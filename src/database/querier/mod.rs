@@ -1,3 +1,11 @@
 pub mod book;
 pub mod category;
+pub mod debt;
+pub mod goal;
+pub mod invitation;
+pub mod payee;
 pub mod record;
+pub mod recurring;
+pub mod report;
+pub mod rule;
+pub mod split;
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use sqlx_core::error::BoxDynError;
+use ulid::Ulid;
+
+use crate::database::model::payee::{Payee, PayeeTotal};
+
+// payees are matched by name without case, so "Grab" and "grab" are the same;
+// the existing payee is returned as is, keeping the case it was saved with
+pub async fn find_or_create(
+    pool: &PgPool,
+    book_id: Ulid,
+    name: &str,
+) -> Result<Payee, BoxDynError> {
+    let payee = Payee::new(name, book_id);
+    match sqlx::query(
+        "INSERT INTO payees (id, name, book_id) VALUES ($1, $2, $3)
+        ON CONFLICT (book_id, LOWER(name)) WHERE deleted_at IS NULL
+        DO UPDATE SET name = payees.name
+        RETURNING *;",
+    )
+    .bind(payee.id.to_bytes())
+    .bind(&payee.name)
+    .bind(payee.book_id.to_bytes())
+    .fetch_one(pool)
+    .await
+    {
+        Ok(row) => Ok(Payee::from_row(&row).unwrap()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn set_default_category(
+    pool: &PgPool,
+    payee_id: Ulid,
+    category_id: Option<Ulid>,
+) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "UPDATE payees
+        SET default_category_id = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1",
+    )
+    .bind(payee_id.to_bytes())
+    .bind(category_id.map(|id| id.to_bytes()))
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn get_by_id(id: Ulid, pool: &PgPool) -> Option<Payee> {
+    match sqlx::query("SELECT * FROM payees WHERE id = $1 AND deleted_at IS NULL;")
+        .bind(id.to_bytes())
+        .fetch_one(pool)
+        .await
+    {
+        Ok(row) => Some(Payee::from_row(&row).unwrap()),
+        Err(_) => None,
+    }
+}
+
+// payees of the book whose name contains `query`, for autocomplete
+pub async fn search(book_id: Ulid, query: &str, pool: &PgPool) -> Vec<Payee> {
+    match sqlx::query(
+        "SELECT *
+        FROM payees
+        WHERE book_id = $1 AND deleted_at IS NULL AND name ILIKE '%' || $2 || '%'
+        ORDER BY name ASC
+        LIMIT 10;
+        ",
+    )
+    .bind(book_id.to_bytes())
+    .bind(query.trim())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Payee> = Vec::new();
+            for row in v {
+                let p = Payee::from_row(&row).unwrap();
+                datas.push(p)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+// expense totals per payee of the book within the range, payees without
+// spending in the range are listed last with zero
+pub async fn totals(
+    pool: &PgPool,
+    book_id: Ulid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<PayeeTotal> {
+    match sqlx::query(
+        "
+        SELECT payees.*,
+            COALESCE(SUM(records.amount) FILTER (WHERE categories.is_expense), 0)::FLOAT4 AS total,
+            COUNT(records.id) FILTER (WHERE categories.is_expense) AS record_count
+        FROM payees
+        LEFT JOIN records ON records.payee_id = payees.id
+            AND records.deleted_at IS NULL
+            AND records.created_at >= $2
+            AND records.created_at < $3
+        LEFT JOIN categories ON records.category_id = categories.id
+        WHERE payees.book_id = $1 AND payees.deleted_at IS NULL
+        GROUP BY payees.id
+        ORDER BY total DESC, payees.name ASC;
+    ",
+    )
+    .bind(book_id.to_bytes())
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<PayeeTotal> = Vec::new();
+            for row in v {
+                let t = PayeeTotal::from_row(&row).unwrap();
+                datas.push(t)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
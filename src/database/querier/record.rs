@@ -1,18 +1,22 @@
+use chrono::{DateTime, Utc};
 use sqlx::{FromRow, PgPool};
 use sqlx_core::error::BoxDynError;
 use ulid::Ulid;
 
-use crate::database::model::record::Record;
+use crate::database::{model::record::Record, querier::report::CATEGORY_ROOTS};
 
 pub async fn save(pool: &PgPool, r: Record) -> Result<(), BoxDynError> {
     match sqlx::query(
-        "INSERT INTO records (id, notes, amount, category_id, book_id) VALUES ($1, $2, $3, $4, $5) RETURNING *;",
+        "INSERT INTO records (id, notes, amount, category_id, book_id, payee_id, created_by, category_from_rule) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *;",
     )
     .bind(r.id.to_bytes())
     .bind(r.notes)
     .bind(r.amount)
     .bind(r.category_id.to_bytes())
     .bind(r.book_id.to_bytes())
+    .bind(r.payee_id.map(|id| id.to_bytes()))
+    .bind(r.created_by.map(|id| id.to_bytes()))
+    .bind(r.category_from_rule)
     .execute(pool)
     .await {
         Ok(_) => Ok(()),
@@ -20,26 +24,35 @@ pub async fn save(pool: &PgPool, r: Record) -> Result<(), BoxDynError> {
     }
 }
 
+// writes the record only while it is still at `version`; false when someone
+// changed it since, a trigger moves the version on with every update; moving
+// the record to another category marks the category as chosen by hand
 pub async fn edit(
     pool: &PgPool,
     notes: String,
     amount: f32,
     record_id: Ulid,
     category_id: Ulid,
-) -> Result<(), BoxDynError> {
+    payee_id: Option<Ulid>,
+    version: i32,
+) -> Result<bool, BoxDynError> {
     match sqlx::query(
         "UPDATE records
-                SET notes = $2, amount = $3, category_id = $4, updated_at = CURRENT_TIMESTAMP
-                WHERE id = $1",
+                SET notes = $2, amount = $3, category_id = $4, payee_id = $5,
+                    category_from_rule = category_from_rule AND category_id = $4,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND version = $6",
     )
     .bind(record_id.to_bytes())
     .bind(notes)
     .bind(amount)
     .bind(category_id.to_bytes())
+    .bind(payee_id.map(|id| id.to_bytes()))
+    .bind(version)
     .execute(pool)
     .await
     {
-        Ok(_) => Ok(()),
+        Ok(v) => Ok(v.rows_affected() > 0),
         Err(e) => Err(Box::new(e)),
     }
 }
@@ -73,9 +86,12 @@ pub async fn fetch(book_id: Ulid, cursor: &str, count: i32, pool: PgPool) -> Vec
     };
     match sqlx::query(
         "
-        SELECT records.*, categories.name AS category_name
+        SELECT records.*, categories.name AS category_name,
+            categories.color AS category_color, categories.icon AS category_icon,
+            payees.name AS payee_name
         FROM records
         JOIN categories ON records.category_id = categories.id
+        LEFT JOIN payees ON records.payee_id = payees.id
         WHERE records.book_id = $1 AND records.deleted_at IS NULL AND records.id < $2
         ORDER BY records.id DESC
         LIMIT $3;        
@@ -99,11 +115,51 @@ pub async fn fetch(book_id: Ulid, cursor: &str, count: i32, pool: PgPool) -> Vec
     }
 }
 
+// the latest records of every book the account is a member of, newest first
+pub async fn fetch_by_account(account_id: Ulid, count: i32, pool: &PgPool) -> Vec<Record> {
+    match sqlx::query(
+        "
+        SELECT records.*, categories.name AS category_name,
+            categories.color AS category_color, categories.icon AS category_icon,
+            payees.name AS payee_name, books.name AS book_name
+        FROM records
+        JOIN account_books ON records.book_id = account_books.book_id
+        JOIN books ON records.book_id = books.id
+        JOIN categories ON records.category_id = categories.id
+        LEFT JOIN payees ON records.payee_id = payees.id
+        WHERE account_books.account_id = $1
+            AND account_books.deleted_at IS NULL
+            AND books.deleted_at IS NULL
+            AND records.deleted_at IS NULL
+        ORDER BY records.created_at DESC, records.id DESC
+        LIMIT $2;
+    ",
+    )
+    .bind(account_id.to_bytes())
+    .bind(count)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Record> = Vec::new();
+            for record in v {
+                let b = Record::from_row(&record).unwrap();
+                datas.push(b)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
 pub async fn get_by_id(id: Ulid, pool: PgPool) -> Option<Record> {
     match sqlx::query(
-        "SELECT records.*, categories.name AS category_name
+        "SELECT records.*, categories.name AS category_name,
+            categories.color AS category_color, categories.icon AS category_icon,
+            payees.name AS payee_name
         FROM records
         JOIN categories ON records.category_id = categories.id
+        LEFT JOIN payees ON records.payee_id = payees.id
         WHERE records.id = $1 AND records.deleted_at IS NULL;
         ",
     )
@@ -118,3 +174,49 @@ pub async fn get_by_id(id: Ulid, pool: PgPool) -> Option<Record> {
         Err(_) => None,
     }
 }
+
+// records counted in the report row of the top-level category ($2), using
+// the same roll-up as the report totals so the rows add up to the row total
+pub async fn fetch_by_category(
+    book_id: Ulid,
+    category_id: Ulid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    pool: &PgPool,
+) -> Vec<Record> {
+    match sqlx::query(&format!(
+        "{}
+        SELECT records.*, categories.name AS category_name,
+            categories.color AS category_color, categories.icon AS category_icon,
+            payees.name AS payee_name
+        FROM records
+        JOIN category_roots ON records.category_id = category_roots.id
+        JOIN categories ON records.category_id = categories.id
+        LEFT JOIN payees ON records.payee_id = payees.id
+        WHERE records.book_id = $1
+            AND category_roots.root_id = $2
+            AND records.deleted_at IS NULL
+            AND records.created_at >= $3
+            AND records.created_at < $4
+        ORDER BY records.id DESC;
+    ",
+        CATEGORY_ROOTS
+    ))
+    .bind(book_id.to_bytes())
+    .bind(category_id.to_bytes())
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Record> = Vec::new();
+            for record in v {
+                let b = Record::from_row(&record).unwrap();
+                datas.push(b)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
@@ -0,0 +1,81 @@
+use sqlx::{FromRow, PgPool};
+use sqlx_core::error::BoxDynError;
+use ulid::Ulid;
+
+use crate::database::model::recurring::Recurring;
+
+pub async fn save(pool: &PgPool, r: Recurring) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "INSERT INTO recurrings (id, notes, amount, day_of_month, category_id, book_id) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *;",
+    )
+    .bind(r.id.to_bytes())
+    .bind(r.notes)
+    .bind(r.amount)
+    .bind(r.day_of_month)
+    .bind(r.category_id.to_bytes())
+    .bind(r.book_id.to_bytes())
+    .execute(pool)
+    .await {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn delete(pool: &PgPool, recurring_id: Ulid) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "UPDATE recurrings
+        SET deleted_at = CURRENT_TIMESTAMP
+        WHERE id = $1",
+    )
+    .bind(recurring_id.to_bytes())
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn get_by_id(id: Ulid, pool: &PgPool) -> Option<Recurring> {
+    match sqlx::query(
+        "
+        SELECT recurrings.*, categories.name AS category_name, categories.is_expense
+        FROM recurrings
+        JOIN categories ON recurrings.category_id = categories.id
+        WHERE recurrings.id = $1 AND recurrings.deleted_at IS NULL;
+    ",
+    )
+    .bind(id.to_bytes())
+    .fetch_one(pool)
+    .await
+    {
+        Ok(row) => Some(Recurring::from_row(&row).unwrap()),
+        Err(_) => None,
+    }
+}
+
+pub async fn get_by_book_id(id: Ulid, pool: &PgPool) -> Vec<Recurring> {
+    match sqlx::query(
+        "
+        SELECT recurrings.*, categories.name AS category_name, categories.is_expense
+        FROM recurrings
+        JOIN categories ON recurrings.category_id = categories.id
+        WHERE recurrings.book_id = $1 AND recurrings.deleted_at IS NULL
+        ORDER BY recurrings.day_of_month ASC;
+    ",
+    )
+    .bind(id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Recurring> = Vec::new();
+            for row in v {
+                let r = Recurring::from_row(&row).unwrap();
+                datas.push(r)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
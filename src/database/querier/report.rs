@@ -0,0 +1,175 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool, Row};
+use ulid::Ulid;
+
+use crate::database::model::{
+    book::BookSettings,
+    report::{Bucket, CategoryBucketTotal, CategoryTotal},
+};
+
+// maps every category of the book ($1) to its top-level ancestor
+pub static CATEGORY_ROOTS: &str = "
+    WITH RECURSIVE category_roots AS (
+        SELECT id, id AS root_id
+        FROM categories
+        WHERE book_id = $1 AND parent_id IS NULL
+        UNION ALL
+        SELECT categories.id, category_roots.root_id
+        FROM categories
+        JOIN category_roots ON categories.parent_id = category_roots.id
+    )
+";
+
+pub async fn category_totals(
+    pool: &PgPool,
+    book_id: Ulid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<CategoryTotal> {
+    match sqlx::query(
+        "
+        SELECT categories.id AS category_id, categories.name, categories.is_expense,
+            SUM(records.amount) AS total, COUNT(records.id) AS record_count
+        FROM records
+        JOIN categories ON records.category_id = categories.id
+        WHERE records.book_id = $1
+            AND records.deleted_at IS NULL
+            AND records.created_at >= $2
+            AND records.created_at < $3
+        GROUP BY categories.id, categories.name, categories.is_expense
+        ORDER BY total DESC;
+    ",
+    )
+    .bind(book_id.to_bytes())
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<CategoryTotal> = Vec::new();
+            for row in v {
+                let t = CategoryTotal::from_row(&row).unwrap();
+                datas.push(t)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+// like `category_totals`, with sub-category totals rolled up into their
+// top-level category
+pub async fn rolled_up_category_totals(
+    pool: &PgPool,
+    book_id: Ulid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<CategoryTotal> {
+    match sqlx::query(&format!(
+        "{}
+        SELECT roots.id AS category_id, roots.name, roots.is_expense, roots.color, roots.icon,
+            SUM(records.amount) AS total, COUNT(records.id) AS record_count
+        FROM records
+        JOIN category_roots ON records.category_id = category_roots.id
+        JOIN categories AS roots ON category_roots.root_id = roots.id
+        WHERE records.book_id = $1
+            AND records.deleted_at IS NULL
+            AND records.created_at >= $2
+            AND records.created_at < $3
+        GROUP BY roots.id, roots.name, roots.is_expense, roots.color, roots.icon
+        ORDER BY total DESC;
+    ",
+        CATEGORY_ROOTS
+    ))
+    .bind(book_id.to_bytes())
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<CategoryTotal> = Vec::new();
+            for row in v {
+                let t = CategoryTotal::from_row(&row).unwrap();
+                datas.push(t)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+// totals per top-level category for every time bucket (month or year) within
+// the range, counted in the book's local days; buckets are shifted back by the
+// month start day before truncating and forward again after
+pub async fn category_bucket_totals(
+    pool: &PgPool,
+    book_id: Ulid,
+    bucket: Bucket,
+    settings: &BookSettings,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<CategoryBucketTotal> {
+    match sqlx::query(&format!(
+        "{}
+        SELECT roots.id AS category_id, roots.name, roots.is_expense,
+            DATE_TRUNC($2, (records.created_at AT TIME ZONE $5) - ($6 - 1) * INTERVAL '1 day')
+                + ($6 - 1) * INTERVAL '1 day' AS bucket,
+            SUM(records.amount) AS total
+        FROM records
+        JOIN category_roots ON records.category_id = category_roots.id
+        JOIN categories AS roots ON category_roots.root_id = roots.id
+        WHERE records.book_id = $1
+            AND records.deleted_at IS NULL
+            AND records.created_at >= $3
+            AND records.created_at < $4
+        GROUP BY roots.id, roots.name, roots.is_expense, bucket
+        ORDER BY bucket ASC;
+    ",
+        CATEGORY_ROOTS
+    ))
+    .bind(book_id.to_bytes())
+    .bind(bucket.as_str())
+    .bind(from)
+    .bind(to)
+    .bind(settings.timezone.name())
+    .bind(settings.month_start_day as i32)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<CategoryBucketTotal> = Vec::new();
+            for row in v {
+                let t = CategoryBucketTotal::from_row(&row).unwrap();
+                datas.push(t)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+// income minus expense of every record created before `before`
+pub async fn balance(pool: &PgPool, book_id: Ulid, before: DateTime<Utc>) -> f32 {
+    match sqlx::query(
+        "
+        SELECT COALESCE(SUM(
+            CASE WHEN categories.is_expense THEN -records.amount ELSE records.amount END
+        ), 0)::FLOAT4 AS balance
+        FROM records
+        JOIN categories ON records.category_id = categories.id
+        WHERE records.book_id = $1
+            AND records.deleted_at IS NULL
+            AND records.created_at < $2;
+    ",
+    )
+    .bind(book_id.to_bytes())
+    .bind(before)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(row) => row.get("balance"),
+        Err(_) => 0.0,
+    }
+}
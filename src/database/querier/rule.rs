@@ -0,0 +1,142 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool, Row};
+use sqlx_core::error::BoxDynError;
+use ulid::Ulid;
+
+use crate::database::model::rule::{first_match, Matcher, Rule, RuleChange};
+
+pub async fn save(pool: &PgPool, r: Rule) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "INSERT INTO rules (id, kind, pattern, min_amount, max_amount, category_id, book_id) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *;",
+    )
+    .bind(r.id.to_bytes())
+    .bind(r.kind.as_str())
+    .bind(r.pattern)
+    .bind(r.min_amount)
+    .bind(r.max_amount)
+    .bind(r.category_id.to_bytes())
+    .bind(r.book_id.to_bytes())
+    .execute(pool)
+    .await {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn delete(pool: &PgPool, rule_id: Ulid) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "UPDATE rules
+        SET deleted_at = CURRENT_TIMESTAMP
+        WHERE id = $1",
+    )
+    .bind(rule_id.to_bytes())
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn get_by_id(id: Ulid, pool: &PgPool) -> Option<Rule> {
+    match sqlx::query("SELECT * FROM rules WHERE id = $1 AND deleted_at IS NULL;")
+        .bind(id.to_bytes())
+        .fetch_one(pool)
+        .await
+    {
+        Ok(row) => Some(Rule::from_row(&row).unwrap()),
+        Err(_) => None,
+    }
+}
+
+pub async fn get_by_book_id(id: Ulid, pool: &PgPool) -> Vec<Rule> {
+    match sqlx::query(
+        "
+        SELECT rules.*, categories.name AS category_name
+        FROM rules
+        JOIN categories ON rules.category_id = categories.id
+        WHERE rules.book_id = $1 AND rules.deleted_at IS NULL
+            AND categories.deleted_at IS NULL
+        ORDER BY rules.id ASC;
+    ",
+    )
+    .bind(id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Rule> = Vec::new();
+            for row in v {
+                let r = Rule::from_row(&row).unwrap();
+                datas.push(r)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+// runs the rules over the live records of the book created between `from`
+// and `to` whose category a rule picked, and moves the matching ones to the
+// category of their first matching rule; categories chosen by hand are kept.
+// Returns the records that changed category
+pub async fn apply(
+    pool: &PgPool,
+    book_id: Ulid,
+    rules: &[Rule],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<RuleChange>, BoxDynError> {
+    let matchers: Vec<Matcher> = rules.iter().map(Rule::matcher).collect();
+    let mut tx = pool.begin().await.unwrap();
+    let rows = match sqlx::query(
+        "SELECT id, notes, amount, category_id
+        FROM records
+        WHERE book_id = $1 AND deleted_at IS NULL AND category_from_rule
+            AND created_at >= $2 AND created_at < $3
+        ORDER BY id",
+    )
+    .bind(book_id.to_bytes())
+    .bind(from)
+    .bind(to)
+    .fetch_all(&mut *tx)
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => {
+            tx.rollback().await.unwrap();
+            return Err(Box::new(e));
+        }
+    };
+    let mut changed: Vec<RuleChange> = Vec::new();
+    for row in rows {
+        let id: [u8; 16] = row.get("id");
+        let notes: String = row.get("notes");
+        let amount: f32 = row.get("amount");
+        let category_id: [u8; 16] = row.get("category_id");
+        let rule = match first_match(&matchers, &notes, amount) {
+            Some(r) if r.category_id.to_bytes() != category_id => r,
+            _ => continue,
+        };
+        if let Err(e) = sqlx::query(
+            "UPDATE records
+            SET category_id = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1",
+        )
+        .bind(id)
+        .bind(rule.category_id.to_bytes())
+        .execute(&mut *tx)
+        .await
+        {
+            tx.rollback().await.unwrap();
+            return Err(Box::new(e));
+        }
+        changed.push(RuleChange {
+            record_id: Ulid::from_bytes(id),
+            notes,
+            category_name: rule.category_name.clone(),
+        });
+    }
+    tx.commit().await.unwrap();
+    Ok(changed)
+}
@@ -0,0 +1,228 @@
+use sqlx::{FromRow, PgPool};
+use sqlx_core::error::BoxDynError;
+use ulid::Ulid;
+
+use crate::database::model::split::{MemberBalance, Settlement, Share, Split};
+
+// a record keeps at most one active split, saving a new one replaces it
+pub async fn save(pool: &PgPool, s: Split, shares: Vec<Share>) -> Result<(), BoxDynError> {
+    let mut tx = pool.begin().await.unwrap();
+    if let Err(err) = sqlx::query(
+        "UPDATE record_splits
+        SET deleted_at = CURRENT_TIMESTAMP
+        WHERE record_id = $1 AND deleted_at IS NULL",
+    )
+    .bind(s.record_id.to_bytes())
+    .execute(&mut *tx)
+    .await
+    {
+        tx.rollback().await.unwrap();
+        return Err(Box::new(err));
+    }
+    match sqlx::query(
+        "INSERT INTO record_splits (id, method, paid_by, record_id) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(s.id.to_bytes())
+    .bind(s.method.as_str())
+    .bind(s.paid_by.to_bytes())
+    .bind(s.record_id.to_bytes())
+    .execute(&mut *tx)
+    .await
+    {
+        Ok(_) => {
+            for share in shares {
+                if let Err(err) = sqlx::query(
+                    "INSERT INTO record_split_shares (split_id, account_id, amount) VALUES ($1, $2, $3)",
+                )
+                .bind(share.split_id.to_bytes())
+                .bind(share.account_id.to_bytes())
+                .bind(share.amount)
+                .execute(&mut *tx)
+                .await
+                {
+                    tx.rollback().await.unwrap();
+                    return Err(Box::new(err));
+                }
+            }
+            tx.commit().await.unwrap();
+            Ok(())
+        }
+        Err(err) => {
+            tx.rollback().await.unwrap();
+            Err(Box::new(err))
+        }
+    }
+}
+
+// replaces the shares of a split, e.g. after its record amount changed
+pub async fn set_shares(
+    pool: &PgPool,
+    split_id: Ulid,
+    shares: Vec<Share>,
+) -> Result<(), BoxDynError> {
+    let mut tx = pool.begin().await.unwrap();
+    if let Err(err) = sqlx::query("DELETE FROM record_split_shares WHERE split_id = $1")
+        .bind(split_id.to_bytes())
+        .execute(&mut *tx)
+        .await
+    {
+        tx.rollback().await.unwrap();
+        return Err(Box::new(err));
+    }
+    for share in shares {
+        if let Err(err) = sqlx::query(
+            "INSERT INTO record_split_shares (split_id, account_id, amount) VALUES ($1, $2, $3)",
+        )
+        .bind(share.split_id.to_bytes())
+        .bind(share.account_id.to_bytes())
+        .bind(share.amount)
+        .execute(&mut *tx)
+        .await
+        {
+            tx.rollback().await.unwrap();
+            return Err(Box::new(err));
+        }
+    }
+    tx.commit().await.unwrap();
+    Ok(())
+}
+
+pub async fn delete(pool: &PgPool, record_id: Ulid) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "UPDATE record_splits
+        SET deleted_at = CURRENT_TIMESTAMP
+        WHERE record_id = $1 AND deleted_at IS NULL",
+    )
+    .bind(record_id.to_bytes())
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn get_by_record_id(record_id: Ulid, pool: &PgPool) -> Option<Split> {
+    match sqlx::query("SELECT * FROM record_splits WHERE record_id = $1 AND deleted_at IS NULL;")
+        .bind(record_id.to_bytes())
+        .fetch_one(pool)
+        .await
+    {
+        Ok(v) => {
+            let split = Split::from_row(&v).unwrap();
+            Some(split)
+        }
+        Err(_) => None,
+    }
+}
+
+pub async fn get_shares(split_id: Ulid, pool: &PgPool) -> Vec<Share> {
+    match sqlx::query("SELECT * FROM record_split_shares WHERE split_id = $1;")
+        .bind(split_id.to_bytes())
+        .fetch_all(pool)
+        .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Share> = Vec::new();
+            for row in v {
+                let s = Share::from_row(&row).unwrap();
+                datas.push(s)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+// net balance of every member with split records or settlements in the book:
+// the payer is owed the other members' shares, and a settlement moves the
+// balance from the payer of the reimbursement to its receiver
+pub async fn balances(book_id: Ulid, pool: &PgPool) -> Vec<MemberBalance> {
+    match sqlx::query(
+        "
+        WITH shares AS (
+            SELECT record_splits.paid_by, record_split_shares.account_id, record_split_shares.amount
+            FROM record_split_shares
+            JOIN record_splits ON record_split_shares.split_id = record_splits.id
+            JOIN records ON record_splits.record_id = records.id
+            WHERE records.book_id = $1
+                AND records.deleted_at IS NULL
+                AND record_splits.deleted_at IS NULL
+                AND record_split_shares.account_id <> record_splits.paid_by
+        ), transfers AS (
+            SELECT from_account_id, to_account_id, amount
+            FROM settlements
+            WHERE book_id = $1 AND deleted_at IS NULL
+        )
+        SELECT account_id, SUM(net)::FLOAT4 AS net
+        FROM (
+            SELECT paid_by AS account_id, amount AS net FROM shares
+            UNION ALL
+            SELECT account_id, -amount AS net FROM shares
+            UNION ALL
+            SELECT from_account_id AS account_id, amount AS net FROM transfers
+            UNION ALL
+            SELECT to_account_id AS account_id, -amount AS net FROM transfers
+        ) AS movements
+        GROUP BY account_id;
+    ",
+    )
+    .bind(book_id.to_bytes())
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<MemberBalance> = Vec::new();
+            for row in v {
+                let b = MemberBalance::from_row(&row).unwrap();
+                datas.push(b)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
+
+pub async fn save_settlement(pool: &PgPool, s: Settlement) -> Result<(), BoxDynError> {
+    match sqlx::query(
+        "INSERT INTO settlements (id, amount, from_account_id, to_account_id, book_id) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(s.id.to_bytes())
+    .bind(s.amount)
+    .bind(s.from_account_id.to_bytes())
+    .bind(s.to_account_id.to_bytes())
+    .bind(s.book_id.to_bytes())
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+pub async fn get_settlements(book_id: Ulid, count: i32, pool: &PgPool) -> Vec<Settlement> {
+    match sqlx::query(
+        "
+        SELECT *
+        FROM settlements
+        WHERE book_id = $1 AND deleted_at IS NULL
+        ORDER BY id DESC
+        LIMIT $2;
+    ",
+    )
+    .bind(book_id.to_bytes())
+    .bind(count)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(v) => {
+            let mut datas: Vec<Settlement> = Vec::new();
+            for row in v {
+                let s = Settlement::from_row(&row).unwrap();
+                datas.push(s)
+            }
+            datas
+        }
+        Err(_) => [].to_vec(),
+    }
+}
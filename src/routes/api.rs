@@ -1,8 +1,27 @@
 use crate::app::api::account::{create_account, validate_email, validate_password};
-use crate::app::api::book::{add_book_owner, create_book, delete_book, edit_book};
-use crate::app::api::category::{create_category, delete_category, edit_category};
+use crate::app::api::book::{
+    create_book, delete_book, delete_member, duplicate_book, edit_book, edit_member_role,
+    invite_to_book, leave_book, switch_book,
+};
+use crate::app::api::category::{
+    archive_category, create_category, delete_category, edit_category, merge_category,
+};
+use crate::app::api::debt::{create_debt, create_debt_payment, delete_debt, delete_debt_payment};
+use crate::app::api::event::stream_book_events;
+use crate::app::api::goal::{
+    create_goal, create_goal_contribution, delete_goal, delete_goal_contribution,
+};
+use crate::app::api::invitation::{accept_invitation, decline_invitation};
+use crate::app::api::payee::edit_payee;
 use crate::app::api::record::{create_record, delete_record, edit_record};
+use crate::app::api::recurring::{create_recurring, delete_recurring};
+use crate::app::api::report::{
+    get_forecast, get_overview, get_report, get_report_records, get_trend,
+};
+use crate::app::api::rule::{apply_rules, create_rule, delete_rule};
 use crate::app::api::session::{login_account, logout_account};
+use crate::app::api::split::{delete_split, save_split, settle_up};
+use crate::app::middlewares::book::book_middleware;
 use crate::app::middlewares::session::auth_middleware;
 use crate::utils::serve_empty;
 use http_body_util::combinators::BoxBody;
@@ -26,14 +45,62 @@ pub async fn api_routes(
         (&Method::POST, "/api/book") => auth_middleware(req, pool, create_book).await,
         (&Method::PATCH, "/api/book") => auth_middleware(req, pool, edit_book).await,
         (&Method::DELETE, "/api/book") => auth_middleware(req, pool, delete_book).await,
-        (&Method::POST, "/api/book/add-owner") => auth_middleware(req, pool, add_book_owner).await,
+        (&Method::PATCH, "/api/book/member") => auth_middleware(req, pool, edit_member_role).await,
+        (&Method::DELETE, "/api/book/member") => auth_middleware(req, pool, delete_member).await,
+        (&Method::POST, "/api/book/duplicate") => auth_middleware(req, pool, duplicate_book).await,
+        (&Method::GET, "/api/book/events") => book_middleware(req, pool, stream_book_events).await,
+        (&Method::POST, "/api/book/invite") => auth_middleware(req, pool, invite_to_book).await,
+        (&Method::POST, "/api/book/leave") => auth_middleware(req, pool, leave_book).await,
+        (&Method::POST, "/api/book/switch") => auth_middleware(req, pool, switch_book).await,
+        (&Method::POST, "/api/invitation/accept") => {
+            auth_middleware(req, pool, accept_invitation).await
+        }
+        (&Method::POST, "/api/invitation/decline") => {
+            auth_middleware(req, pool, decline_invitation).await
+        }
 
-        (&Method::POST, "/api/category") => auth_middleware(req, pool, create_category).await,
-        (&Method::DELETE, "/api/category") => auth_middleware(req, pool, delete_category).await,
-        (&Method::PATCH, "/api/category") => auth_middleware(req, pool, edit_category).await,
-        (&Method::POST, "/api/record") => auth_middleware(req, pool, create_record).await,
-        (&Method::PATCH, "/api/record") => auth_middleware(req, pool, edit_record).await,
-        (&Method::DELETE, "/api/record") => auth_middleware(req, pool, delete_record).await,
+        (&Method::POST, "/api/category") => book_middleware(req, pool, create_category).await,
+        (&Method::DELETE, "/api/category") => book_middleware(req, pool, delete_category).await,
+        (&Method::PATCH, "/api/category") => book_middleware(req, pool, edit_category).await,
+        (&Method::PATCH, "/api/category/archive") => {
+            book_middleware(req, pool, archive_category).await
+        }
+        (&Method::POST, "/api/category/merge") => book_middleware(req, pool, merge_category).await,
+        (&Method::POST, "/api/record") => book_middleware(req, pool, create_record).await,
+        (&Method::PATCH, "/api/record") => book_middleware(req, pool, edit_record).await,
+        (&Method::DELETE, "/api/record") => book_middleware(req, pool, delete_record).await,
+        (&Method::GET, "/api/report") => book_middleware(req, pool, get_report).await,
+        (&Method::GET, "/api/report/records") => {
+            book_middleware(req, pool, get_report_records).await
+        }
+        (&Method::GET, "/api/report/trend") => book_middleware(req, pool, get_trend).await,
+        (&Method::GET, "/api/overview") => auth_middleware(req, pool, get_overview).await,
+        (&Method::GET, "/api/forecast") => book_middleware(req, pool, get_forecast).await,
+        (&Method::POST, "/api/goal") => book_middleware(req, pool, create_goal).await,
+        (&Method::DELETE, "/api/goal") => book_middleware(req, pool, delete_goal).await,
+        (&Method::POST, "/api/goal/contribution") => {
+            book_middleware(req, pool, create_goal_contribution).await
+        }
+        (&Method::DELETE, "/api/goal/contribution") => {
+            book_middleware(req, pool, delete_goal_contribution).await
+        }
+        (&Method::POST, "/api/debt") => book_middleware(req, pool, create_debt).await,
+        (&Method::DELETE, "/api/debt") => book_middleware(req, pool, delete_debt).await,
+        (&Method::POST, "/api/debt/payment") => {
+            book_middleware(req, pool, create_debt_payment).await
+        }
+        (&Method::DELETE, "/api/debt/payment") => {
+            book_middleware(req, pool, delete_debt_payment).await
+        }
+        (&Method::POST, "/api/split") => book_middleware(req, pool, save_split).await,
+        (&Method::DELETE, "/api/split") => book_middleware(req, pool, delete_split).await,
+        (&Method::POST, "/api/split/settle") => book_middleware(req, pool, settle_up).await,
+        (&Method::POST, "/api/recurring") => book_middleware(req, pool, create_recurring).await,
+        (&Method::DELETE, "/api/recurring") => book_middleware(req, pool, delete_recurring).await,
+        (&Method::PATCH, "/api/payee") => book_middleware(req, pool, edit_payee).await,
+        (&Method::POST, "/api/rule") => book_middleware(req, pool, create_rule).await,
+        (&Method::DELETE, "/api/rule") => book_middleware(req, pool, delete_rule).await,
+        (&Method::POST, "/api/rule/apply") => book_middleware(req, pool, apply_rules).await,
         _ => {
             let mut not_found = Response::new(serve_empty());
             *not_found.status_mut() = StatusCode::NOT_FOUND;
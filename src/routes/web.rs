@@ -1,11 +1,25 @@
+use crate::app::middlewares::book::book_middleware;
 use crate::app::middlewares::params::id_params_middleware;
 use crate::app::middlewares::session::auth_middleware;
-use crate::app::web::book::{page_book_add_owner, page_book_create, page_book_edit, page_books};
+use crate::app::web::book::{
+    fragment_book_switcher, page_book_create, page_book_edit, page_book_invite, page_books,
+};
 use crate::app::web::category::{page_categories, page_category_create, page_category_edit};
 use crate::app::web::common::{
     image, page_dashboard, page_index, page_not_found, page_signin, page_signup, string_handler,
 };
-use crate::app::web::record::{page_record_create, page_record_edit, page_records};
+use crate::app::web::debt::{page_debt_create, page_debt_detail, page_debts};
+use crate::app::web::goal::{page_goal_create, page_goal_detail, page_goals};
+use crate::app::web::payee::{fragment_payee_options, page_payees};
+use crate::app::web::record::{
+    fragment_category_suggestions, page_record_create, page_record_edit, page_records,
+};
+use crate::app::web::recurring::{page_recurring_create, page_recurrings};
+use crate::app::web::report::{
+    page_forecast, page_overview, page_report, page_report_category, page_trend,
+};
+use crate::app::web::rule::{page_rule_create, page_rules};
+use crate::app::web::split::{page_split_record, page_splits};
 use crate::utils::serve_empty;
 use http_body_util::combinators::BoxBody;
 use hyper::body::{Bytes, Incoming};
@@ -28,6 +42,9 @@ pub async fn web_routes(
         // book routes
         (&Method::GET, "/book") => auth_middleware(req, pool, page_books).await,
         (&Method::GET, "/book/create") => auth_middleware(req, pool, page_book_create).await,
+        (&Method::GET, "/book/switcher") => {
+            auth_middleware(req, pool, fragment_book_switcher).await
+        }
         (&Method::GET, path) if path.starts_with("/book/edit/") => {
             let p = path;
             let run = move |req: Request<Incoming>, pool: PgPool, _: Ulid| async move {
@@ -44,14 +61,12 @@ pub async fn web_routes(
 
             auth_middleware(req, pool, run).await
         }
-        (&Method::GET, "/book/add-owner") | (&Method::GET, "/book/add-book-owner.html") => {
-            page_book_add_owner(req, pool).await
-        }
+        (&Method::GET, "/book/invite") => book_middleware(req, pool, page_book_invite).await,
 
         // category routes
-        (&Method::GET, "/category") => auth_middleware(req, pool, page_categories).await,
+        (&Method::GET, "/category") => book_middleware(req, pool, page_categories).await,
         (&Method::GET, "/category/create") => {
-            auth_middleware(req, pool, page_category_create).await
+            book_middleware(req, pool, page_category_create).await
         }
         (&Method::GET, path) if path.starts_with("/category/edit/") => {
             let p = path;
@@ -67,11 +82,14 @@ pub async fn web_routes(
                 .await
             };
 
-            auth_middleware(req, pool, run).await
+            book_middleware(req, pool, run).await
         }
 
-        (&Method::GET, "/record") => auth_middleware(req, pool, page_records).await,
-        (&Method::GET, "/record/create") => auth_middleware(req, pool, page_record_create).await,
+        (&Method::GET, "/record") => book_middleware(req, pool, page_records).await,
+        (&Method::GET, "/record/create") => book_middleware(req, pool, page_record_create).await,
+        (&Method::GET, "/record/suggest") => {
+            book_middleware(req, pool, fragment_category_suggestions).await
+        }
         (&Method::GET, path) if path.starts_with("/record/edit/") => {
             let p = path;
             let run = move |req: Request<Incoming>, pool: PgPool, _: Ulid| async move {
@@ -86,7 +104,90 @@ pub async fn web_routes(
                 .await
             };
 
-            auth_middleware(req, pool, run).await
+            book_middleware(req, pool, run).await
+        }
+        (&Method::GET, path) if path.starts_with("/record/split/") => {
+            let p = path;
+            let run = move |req: Request<Incoming>, pool: PgPool, _: Ulid| async move {
+                id_params_middleware(
+                    req,
+                    pool,
+                    14,
+                    "/record".to_string(),
+                    p.to_owned(),
+                    page_split_record,
+                )
+                .await
+            };
+
+            book_middleware(req, pool, run).await
+        }
+        (&Method::GET, "/split") => book_middleware(req, pool, page_splits).await,
+        (&Method::GET, "/overview") => auth_middleware(req, pool, page_overview).await,
+        (&Method::GET, "/report") => book_middleware(req, pool, page_report).await,
+        (&Method::GET, "/report/trend") => book_middleware(req, pool, page_trend).await,
+        (&Method::GET, "/forecast") => book_middleware(req, pool, page_forecast).await,
+        (&Method::GET, "/goal") => book_middleware(req, pool, page_goals).await,
+        (&Method::GET, "/goal/create") => book_middleware(req, pool, page_goal_create).await,
+        (&Method::GET, path) if path.starts_with("/goal/") => {
+            let p = path;
+            let run = move |req: Request<Incoming>, pool: PgPool, _: Ulid| async move {
+                id_params_middleware(
+                    req,
+                    pool,
+                    6,
+                    "/goal".to_string(),
+                    p.to_owned(),
+                    page_goal_detail,
+                )
+                .await
+            };
+
+            book_middleware(req, pool, run).await
+        }
+        (&Method::GET, "/debt") => book_middleware(req, pool, page_debts).await,
+        (&Method::GET, "/debt/create") => book_middleware(req, pool, page_debt_create).await,
+        (&Method::GET, path) if path.starts_with("/debt/") => {
+            let p = path;
+            let run = move |req: Request<Incoming>, pool: PgPool, _: Ulid| async move {
+                id_params_middleware(
+                    req,
+                    pool,
+                    6,
+                    "/debt".to_string(),
+                    p.to_owned(),
+                    page_debt_detail,
+                )
+                .await
+            };
+
+            book_middleware(req, pool, run).await
+        }
+        (&Method::GET, "/recurring") => book_middleware(req, pool, page_recurrings).await,
+        (&Method::GET, "/recurring/create") => {
+            book_middleware(req, pool, page_recurring_create).await
+        }
+        (&Method::GET, "/payee") => book_middleware(req, pool, page_payees).await,
+        (&Method::GET, "/payee/options") => {
+            book_middleware(req, pool, fragment_payee_options).await
+        }
+        (&Method::GET, "/rule") => book_middleware(req, pool, page_rules).await,
+        (&Method::GET, "/rule/create") => book_middleware(req, pool, page_rule_create).await,
+        (&Method::GET, path) if path.starts_with("/report/category/") => {
+            let p = path;
+            let run = move |req: Request<Incoming>, pool: PgPool, _: Ulid| async move {
+                id_params_middleware(
+                    req,
+                    pool,
+                    17,
+                    "/report".to_string(),
+                    p.to_owned(),
+                    page_report_category,
+                )
+                .await
+            };
+
+            book_middleware(req, pool, run).await
         }
         (&Method::GET, "/dashboard") | (&Method::GET, "/dashboard.html") => {
             page_dashboard(req, pool).await
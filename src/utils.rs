@@ -1,13 +1,16 @@
+use std::collections::HashMap;
 use std::convert::Infallible;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 use http_body_util::{combinators::BoxBody, BodyExt, Empty, Full};
-use hyper::body::Bytes;
+use hyper::body::{Body, Bytes, Frame};
 use num_format::{Locale, ToFormattedString};
-use rand::{Rng, SeedableRng};
+use rand::Rng;
 
+// session tokens come from here, so every call must get its own randomness
 pub fn generate_random_string(len: usize) -> String {
-    let timestamp = chrono::Utc::now().timestamp();
-    let mut rng = rand::rngs::StdRng::seed_from_u64(timestamp as u64);
+    let mut rng = rand::thread_rng();
     let chars = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
     let random_string: String = (0..len)
         .map(|_| {
@@ -44,6 +47,39 @@ pub fn serve_full<T: Into<Bytes>>(chunk: T) -> BoxBody<Bytes, Infallible> {
     Full::new(chunk.into()).boxed()
 }
 
+// a body that sends whatever arrives on the channel, it ends once every sender
+// is dropped
+struct ChannelBody {
+    rx: tokio::sync::mpsc::Receiver<Bytes>,
+}
+
+impl Body for ChannelBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.rx
+            .poll_recv(cx)
+            .map(|chunk| chunk.map(|c| Ok(Frame::data(c))))
+    }
+}
+
+pub fn serve_stream(rx: tokio::sync::mpsc::Receiver<Bytes>) -> BoxBody<Bytes, Infallible> {
+    ChannelBody { rx }.boxed()
+}
+
 pub fn format_rupiah(value: f32) -> String {
     format!("Rp {}", (value as u32).to_formatted_string(&Locale::id))
 }
+
+pub fn parse_query(query: Option<&str>) -> HashMap<String, String> {
+    match query {
+        Some(q) => form_urlencoded::parse(q.as_bytes())
+            .into_owned()
+            .collect::<HashMap<String, String>>(),
+        None => HashMap::new(),
+    }
+}